@@ -1,7 +1,13 @@
-use ::api_util::amqp::{DeliveryExt, DeliveryResult};
+use crate::app::get_state;
+use ::api_util::{
+    amqp::{DeliveryExt, DeliveryResult},
+    log,
+};
 
 pub async fn consumer(delivery: DeliveryResult) {
     let Ok(Some(delivery)) = delivery else { return };
 
+    log::handle_log_control(get_state().cfg.name, &delivery);
+
     delivery.confirm()
 }
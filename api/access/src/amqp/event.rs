@@ -0,0 +1,25 @@
+use crate::app::get_state;
+use ::api_util::{
+    amqp::{AMQPMessageOptions, AMQPPoolExt},
+    auth_event::{AuthAction, AuthEvent},
+    log::error,
+};
+
+/// Publishes an authentication event to the topic exchange.
+///
+/// Delivery failures are logged and never abort the request being served.
+pub async fn publish_auth_event(action: AuthAction, event: &AuthEvent) {
+    let state = get_state();
+
+    if let Err(err) = state
+        .amqp
+        .send_auth_event(
+            AMQPMessageOptions::default().with_app_id(state.cfg.name),
+            action,
+            event,
+        )
+        .await
+    {
+        error!("'{}' sending AMQP message: {err}", action.routing_key());
+    }
+}
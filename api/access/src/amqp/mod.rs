@@ -1,8 +1,11 @@
 mod broadcast;
+mod event;
 
 use crate::{Error, app::get_state};
 use ::api_util::amqp::{AMQPChannelOptions, ExchangeKind};
 
+pub use self::event::*;
+
 pub async fn init_amqp() -> Result<(), Error> {
     let state = get_state();
 
@@ -1,5 +1,10 @@
-use crate::middleware::JwtKeys;
+use crate::{
+    middleware::{IdTokenKeys, JwtKeys},
+    model::{SessionLimitPolicy, SessionPolicy},
+};
 use ::api_util::env;
+use ::ipnet::IpNet;
+use ::std::net::IpAddr;
 
 pub struct AppConfig {
     pub name: &'static str,
@@ -12,6 +17,24 @@ pub struct Security {
     pub jwt_keys: JwtKeys,
     pub delete_expired_tokens_interval: u64,
     pub set_cookie: &'static str,
+    pub trusted_proxies: Vec<IpNet>,
+    pub session: SessionPolicy,
+    pub device: DeviceGrant,
+    pub oidc: Oidc,
+}
+
+pub struct DeviceGrant {
+    pub verification_uri: &'static str,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+pub struct Oidc {
+    pub issuer: &'static str,
+    pub login_uri: &'static str,
+    pub code_expires_in: i64,
+    pub id_token_expires_in: i64,
+    pub id_token_keys: IdTokenKeys,
 }
 
 pub struct Jwt {
@@ -38,6 +61,51 @@ impl AppConfig {
 
         let jwt_keys = JwtKeys::new(jwt.secret.as_bytes());
 
+        let session = SessionPolicy {
+            ipv4_prefix: env::get_var_or_default("SESSION_IPV4_PREFIX", "0")
+                .parse::<u8>()
+                .map_or(0, |prefix| prefix.min(32)),
+            ipv6_prefix: env::get_var_or_default("SESSION_IPV6_PREFIX", "0")
+                .parse::<u8>()
+                .map_or(0, |prefix| prefix.min(128)),
+            bind_user_agent: env::get_var_or_default("SESSION_BIND_USER_AGENT", "false")
+                .parse()
+                .unwrap_or(false),
+            limit_policy: env::get_var_or_default("SESSION_LIMIT_POLICY", "evict")
+                .parse()
+                .unwrap_or(SessionLimitPolicy::Evict),
+        };
+
+        let device = DeviceGrant {
+            verification_uri: env::get_var_or_default(
+                "DEVICE_VERIFICATION_URI",
+                "https://localhost/device",
+            ),
+            expires_in: env::get_var_or_default("DEVICE_CODE_EXPIRATION", "600")
+                .parse()
+                .unwrap_or(600),
+            interval: env::get_var_or_default("DEVICE_POLL_INTERVAL", "5")
+                .parse()
+                .unwrap_or(5),
+        };
+
+        let oidc = Oidc {
+            issuer: env::get_var_or_default("OIDC_ISSUER", "https://localhost")
+                .trim_end_matches('/'),
+            login_uri: env::get_var_or_default("OIDC_LOGIN_URI", "https://localhost/login"),
+            code_expires_in: env::get_var_or_default("OIDC_CODE_EXPIRATION", "60")
+                .parse()
+                .unwrap_or(60),
+            id_token_expires_in: env::get_var_or_default("OIDC_ID_TOKEN_EXPIRATION", "600")
+                .parse()
+                .unwrap_or(600),
+            id_token_keys: IdTokenKeys::new(
+                env::get_var("OIDC_ID_TOKEN_KEY")
+                    .filter(|path| !path.is_empty())
+                    .as_deref(),
+            ),
+        };
+
         let security = Security {
             jwt,
             jwt_keys,
@@ -48,6 +116,19 @@ impl AppConfig {
                 "JWT_SET_COOKIE",
                 "SameSite=Strict; HttpOnly; Secure;",
             ),
+            // Only the proxy may be trusted, any other host could forge certificate headers
+            trusted_proxies: env::get_var_or_default("TRUSTED_PROXIES", "")
+                .split(',')
+                .map(str::trim)
+                .filter_map(|net| {
+                    net.parse()
+                        .ok()
+                        .or_else(|| net.parse::<IpAddr>().ok().map(IpNet::from))
+                })
+                .collect(),
+            session,
+            device,
+            oidc,
         };
 
         Self {
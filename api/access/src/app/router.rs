@@ -1,13 +1,128 @@
-use crate::controller::auth;
-use ::api_util::{handler, prometheus};
-use ::axum::{Router, middleware::from_fn, routing::get};
+use crate::controller::{auth, oidc};
+use ::api_util::{handler, prometheus, trace};
+use ::axum::{
+    Router,
+    middleware::from_fn,
+    routing::{delete, get, post},
+};
 use ::axum_reverse_proxy::ReverseProxy;
 
 pub fn init_app() -> Router {
     Router::new()
         .merge(ReverseProxy::new("/api/audit", "http://audit:80"))
+        .merge(ReverseProxy::new("/api/logger", "http://logger:80"))
         .route("/api/auth/token", get(auth::token))
+        .route("/api/auth/certificate", get(auth::certificate))
+        .route("/api/auth/device", post(auth::device_authorize))
+        .route("/api/auth/device/token", post(auth::device_token))
+        .route("/api/auth/device/approve", post(auth::device_approve))
         .route("/api/auth", get(auth::authorize).delete(auth::revoke))
+        .route("/.well-known/openid-configuration", get(oidc::discovery))
+        .route("/api/auth/oidc/authorize", get(oidc::authorize))
+        .route("/api/auth/oidc/token", post(oidc::token))
+        .route(
+            "/api/auth/oidc/userinfo",
+            get(oidc::userinfo).post(oidc::userinfo),
+        )
+        .route("/api/auth/oidc/jwks", get(oidc::jwks))
+        .route("/api/auth/oidc/clients", post(oidc::create_client))
+        .route(
+            "/api/auth/oidc/clients/{client_id}",
+            delete(oidc::delete_client),
+        )
         .route("/healthcheck", get(handler::healthcheck))
         .route_layer(from_fn(prometheus::track_metrics))
+        .layer(from_fn(trace::trace_context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::init_app;
+    use crate::app::init_test_state;
+    use ::api_util::log::{JsonLayer, stdout_layer};
+    use ::std::{
+        io::{self, Write},
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    };
+    use ::tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use ::tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt};
+
+    const SECRET: &str = "hunter2";
+
+    /// Log output kept in memory
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Output {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Output {
+        type Writer = Output;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Status line of the response the router sends to `GET <path>`
+    async fn get(path: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = init_app().into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn keeps_login_passwords_out_of_the_logs() {
+        // The workspace enables several crypto backends, the proxied routes can't pick one
+        let _ = ::rustls::crypto::aws_lc_rs::default_provider().install_default();
+        init_test_state().await;
+        let (printed, shipped) = (Output::default(), Output::default());
+        let _subscriber = tracing_subscriber::registry()
+            .with(stdout_layer(printed.clone()))
+            .with(JsonLayer::new("access", shipped.clone()))
+            .set_default();
+
+        // The database can't be reached, the login fails once the credentials are checked
+        let failed = get(&format!("/api/auth?login=alice&password={SECRET}")).await;
+        // A repeated field is rejected by the query extractor
+        let rejected = get(&format!(
+            "/api/auth?login=alice&login=bob&password={SECRET}"
+        ))
+        .await;
+
+        assert!(!failed.contains(" 200 "), "login succeeded: {failed}");
+        assert!(rejected.contains(" 400 "), "query accepted: {rejected}");
+        for output in [printed.text(), shipped.text()] {
+            assert!(!output.is_empty(), "nothing logged");
+            assert!(!output.contains(SECRET), "password left in {output}");
+        }
+    }
 }
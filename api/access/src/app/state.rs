@@ -1,6 +1,17 @@
 use super::config::AppConfig;
-use crate::repository::PermissionsRepository;
-use ::api_util::{Error, amqp::AMQPPool, amqp_init, db_init, migrate::MigrateExt};
+use crate::{
+    amqp::publish_auth_event, middleware::validate_permissions_consistency,
+    repository::PermissionsRepository,
+};
+use ::api_util::{
+    Error,
+    amqp::AMQPPool,
+    amqp_init,
+    auth::set_claims_validator,
+    auth_event::{AuthAction, AuthEvent},
+    db_init,
+    migrate::MigrateExt,
+};
 use ::surrealdb::{Surreal, engine::remote::ws::Client};
 use ::tokio::sync::{OnceCell, RwLock};
 
@@ -16,10 +27,14 @@ pub struct AppState {
 impl AppState {
     pub async fn update_permissions_map(&self) -> Result<(), Error> {
         let permissions_map = self.db.get_permissions_map().await?;
+        let event = AuthEvent::default()
+            .with_reason(format!("{} permissions loaded", permissions_map.len()));
         {
             *self.permissions_map.write().await = permissions_map;
         }
 
+        publish_auth_event(AuthAction::PermissionsReload, &event).await;
+
         Ok(())
     }
 }
@@ -41,9 +56,26 @@ pub async fn init_state() -> Result<&'static AppState, Error> {
     APP.set(state)
         .map_err(|_| Error::Unknown("Application state already set"))?;
 
+    set_claims_validator(validate_permissions_consistency);
+
     Ok(APP.get().unwrap())
 }
 
 pub fn get_state() -> &'static AppState {
     APP.get().expect("Application state is not set")
 }
+
+/// State of the router tests, neither the database nor the broker can be reached so every
+/// query and publish fails
+#[cfg(test)]
+pub async fn init_test_state() -> &'static AppState {
+    APP.get_or_init(|| async {
+        AppState {
+            cfg: AppConfig::new(),
+            amqp: AMQPPool::new("amqp://127.0.0.1:1").await.unwrap(),
+            db: Surreal::init(),
+            permissions_map: RwLock::new(Vec::new()),
+        }
+    })
+    .await
+}
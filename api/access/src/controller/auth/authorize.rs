@@ -1,15 +1,16 @@
-use super::{COOKIE_JWT, util::build_token_response};
-use crate::{
-    app::get_state,
-    middleware::Claims,
-    repository::{AuthRepository, TokenRepository},
+use super::{
+    COOKIE_JWT,
+    util::{build_token_response, create_session, publish_login_failure, refresh_auth},
 };
-use ::api_util::{AuthError, Error};
+use crate::{app::get_state, middleware::ClientInfo, repository::AuthRepository};
+use ::api_util::{AuthError, Error, auth::Claims};
 use ::axum::{extract::Query, response::IntoResponse};
 use ::axum_extra::extract::CookieJar;
 use ::serde::Deserialize;
 use ::std::borrow::Cow;
 
+const LOGIN_METHOD: &str = "password";
+
 #[derive(Deserialize)]
 pub struct AuthPayload<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,13 +22,13 @@ pub struct AuthPayload<'a> {
 }
 
 pub async fn authorize(
+    client: ClientInfo,
     jar: CookieJar,
     Query(payload): Query<AuthPayload<'_>>,
 ) -> Result<impl IntoResponse, Error> {
     let state = get_state();
-    let refresh_token_uuid;
 
-    let auth = if payload.login.is_none() {
+    let (auth, refresh_token_uuid) = if payload.login.is_none() {
         let cookie = jar.get(COOKIE_JWT).ok_or(AuthError::MissingToken)?;
 
         let current_refresh_token =
@@ -35,20 +36,7 @@ pub async fn authorize(
                 .jti
                 .ok_or(AuthError::MissingToken)?;
 
-        let auth = state
-            .db
-            .find_auth_by_token(current_refresh_token.clone())
-            .await?;
-
-        refresh_token_uuid = state
-            .db
-            .update_refresh_token(
-                current_refresh_token,
-                state.cfg.security.jwt.refresh_expires_in,
-            )
-            .await?;
-
-        auth
+        refresh_auth(current_refresh_token.into_owned(), &client).await?
     } else {
         let AuthPayload {
             login: Some(login),
@@ -56,21 +44,26 @@ pub async fn authorize(
             device,
         } = payload
         else {
-            Err(AuthError::WrongCredentials)?
+            let err = Error::from(AuthError::WrongCredentials);
+            publish_login_failure(payload.login.as_deref(), LOGIN_METHOD, &client, &err).await;
+            Err(err)?
         };
 
-        let auth = state.db.find_auth_by_credentials(login, password).await?;
-
-        refresh_token_uuid = state
+        let auth = match state
             .db
-            .create_refresh_token(
-                auth.id.clone(),
-                state.cfg.security.jwt.refresh_expires_in,
-                device,
-            )
-            .await?;
+            .find_auth_by_credentials(login.clone(), password)
+            .await
+        {
+            Ok(auth) => auth,
+            Err(err) => {
+                publish_login_failure(Some(login.as_ref()), LOGIN_METHOD, &client, &err).await;
+                Err(err)?
+            }
+        };
+
+        let refresh_token_uuid = create_session(&auth.id, device, &client, LOGIN_METHOD).await?;
 
-        auth
+        (auth, refresh_token_uuid)
     };
 
     build_token_response(auth, refresh_token_uuid).await
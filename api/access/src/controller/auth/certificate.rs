@@ -0,0 +1,44 @@
+use super::util::{build_token_response, create_session, publish_login_failure};
+use crate::{app::get_state, middleware::ClientInfo, repository::AuthRepository};
+use ::api_util::{AuthError, Error};
+use ::axum::{extract::Query, response::IntoResponse};
+use ::serde::Deserialize;
+use ::std::borrow::Cow;
+
+const LOGIN_METHOD: &str = "certificate";
+
+#[derive(Deserialize)]
+pub struct CertificatePayload<'a> {
+    #[serde(default)]
+    pub device: Option<Cow<'a, str>>,
+}
+
+/// Issues tokens for the user bound to the client certificate verified by the proxy
+pub async fn certificate(
+    client: ClientInfo,
+    Query(payload): Query<CertificatePayload<'_>>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    let Some(certificate) = client.certificate.as_ref() else {
+        let err = Error::from(AuthError::MissingToken);
+        publish_login_failure(None, LOGIN_METHOD, &client, &err).await;
+        Err(err)?
+    };
+
+    let auth = match state
+        .db
+        .find_auth_by_certificate(certificate.subject.as_str(), certificate.serial.as_str())
+        .await
+    {
+        Ok(auth) => auth,
+        Err(err) => {
+            publish_login_failure(Some(&certificate.subject), LOGIN_METHOD, &client, &err).await;
+            Err(err)?
+        }
+    };
+
+    let refresh_token_uuid =
+        create_session(&auth.id, payload.device, &client, LOGIN_METHOD).await?;
+
+    build_token_response(auth, refresh_token_uuid).await
+}
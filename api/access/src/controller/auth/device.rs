@@ -0,0 +1,255 @@
+use super::util::{build_token_response, create_session};
+use crate::{
+    app::get_state,
+    middleware::ClientInfo,
+    repository::{AuthRepository, DeviceCodeStatus, DeviceRepository},
+};
+use ::api_util::{AuthError, Error, auth::Claims, log::debug};
+use ::axum::{
+    Form, Json,
+    extract::rejection::JsonRejection,
+    http::{
+        StatusCode,
+        header::{CACHE_CONTROL, PRAGMA},
+    },
+    response::{AppendHeaders, IntoResponse, Response},
+};
+use ::chrono::Utc;
+use ::rand::seq::IndexedRandom;
+use ::serde::{Deserialize, Serialize};
+use ::std::borrow::Cow;
+use ::uuid::Uuid;
+
+/// RFC 8628 grant type of the device access token request
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// RFC 8628 recommends a consonant-only charset to avoid ambiguous and offensive codes
+const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+const SLOW_DOWN_INCREMENT: i64 = 5;
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorizationPayload<'a> {
+    #[serde(default)]
+    pub device: Option<Cow<'a, str>>,
+}
+
+#[derive(Serialize)]
+pub struct DeviceAuthorizationBody<'a> {
+    pub device_code: Cow<'a, str>,
+    pub user_code: String,
+    pub verification_uri: &'a str,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceTokenPayload<'a> {
+    pub grant_type: Cow<'a, str>,
+    pub device_code: Cow<'a, str>,
+}
+
+#[derive(Serialize)]
+pub struct DeviceTokenErrorBody<'a> {
+    pub error: &'a str,
+}
+
+/// Error of the device access token request, answered as an RFC 6749 error response.
+///
+/// Pending and slowed down polls are part of the normal flow and are not logged as errors.
+pub struct DeviceTokenError(Error);
+
+impl From<Error> for DeviceTokenError {
+    fn from(err: Error) -> Self {
+        Self(err)
+    }
+}
+
+impl From<AuthError> for DeviceTokenError {
+    fn from(err: AuthError) -> Self {
+        Self(err.into())
+    }
+}
+
+impl IntoResponse for DeviceTokenError {
+    fn into_response(self) -> Response {
+        let Error::AuthError(err) = self.0 else {
+            return self.0.into_response();
+        };
+        debug!("device token request refused: {err}");
+
+        let (code, error) = match err {
+            AuthError::AuthorizationPending
+            | AuthError::SlowDown
+            | AuthError::ExpiredToken
+            | AuthError::AccessDenied
+            | AuthError::InvalidRequest
+            | AuthError::UnsupportedGrantType => (err.status_code(), err.to_string()),
+            // Unknown or already consumed device codes
+            _ => (StatusCode::BAD_REQUEST, AuthError::InvalidGrant.to_string()),
+        };
+
+        (
+            code,
+            AppendHeaders([(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")]),
+            Json(DeviceTokenErrorBody { error: &error }),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeviceApprovePayload<'a> {
+    pub user_code: Cow<'a, str>,
+    #[serde(default = "default_approve")]
+    pub approve: bool,
+}
+
+fn default_approve() -> bool {
+    true
+}
+
+pub async fn device_authorize(
+    payload: Result<Json<DeviceAuthorizationPayload<'_>>, JsonRejection>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    let device = payload.ok().and_then(|Json(payload)| payload.device);
+    let grant = &state.cfg.security.device;
+
+    let user_code = generate_user_code();
+    let device_code = state
+        .db
+        .create_device_code(&user_code, device, grant.interval, grant.expires_in)
+        .await?;
+
+    Ok(Json(DeviceAuthorizationBody {
+        device_code,
+        verification_uri_complete: format!("{}?user_code={user_code}", grant.verification_uri),
+        user_code,
+        verification_uri: grant.verification_uri,
+        expires_in: grant.expires_in,
+        interval: grant.interval,
+    }))
+}
+
+pub async fn device_token(
+    client: ClientInfo,
+    Form(payload): Form<DeviceTokenPayload<'_>>,
+) -> Result<impl IntoResponse, DeviceTokenError> {
+    let state = get_state();
+
+    if payload.grant_type != DEVICE_CODE_GRANT_TYPE {
+        Err(AuthError::UnsupportedGrantType)?
+    }
+    // Device codes are issued as UUIDs, anything else can't name one
+    let device_code = Uuid::parse_str(&payload.device_code)
+        .map_err(|_| AuthError::InvalidGrant)?
+        .to_string();
+
+    let code = state
+        .db
+        .find_device_code(device_code.as_str())
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+
+    let timestamp_now = Utc::now().timestamp();
+    if code.expiration_at < timestamp_now {
+        state.db.delete_device_code(device_code.as_str()).await?;
+        Err(AuthError::ExpiredToken)?
+    }
+
+    match code.status {
+        DeviceCodeStatus::Pending => {
+            let too_fast = code
+                .last_poll_at
+                .is_some_and(|last_poll_at| timestamp_now - last_poll_at < code.interval);
+            let interval = if too_fast {
+                code.interval + SLOW_DOWN_INCREMENT
+            } else {
+                code.interval
+            };
+
+            state
+                .db
+                .update_device_code_poll(device_code.as_str(), interval)
+                .await?;
+
+            if too_fast {
+                Err(AuthError::SlowDown)?
+            }
+            Err(AuthError::AuthorizationPending)?
+        }
+        DeviceCodeStatus::Denied => {
+            state.db.delete_device_code(device_code.as_str()).await?;
+            Err(AuthError::AccessDenied)?
+        }
+        DeviceCodeStatus::Approved => (),
+    }
+
+    // Device codes are single use, only the poll that deletes the approved code gets tokens
+    let code = state
+        .db
+        .consume_device_code(device_code.as_str())
+        .await?
+        .ok_or(AuthError::InvalidGrant)?;
+
+    let auth = state.db.find_auth_by_id(code.user_id).await?;
+    let refresh_token_uuid =
+        create_session(&auth.id, code.device.map(Cow::Owned), &client, "device").await?;
+
+    Ok(build_token_response(auth, refresh_token_uuid).await?)
+}
+
+/// Resolves a device code for the user of a first-party session, tokens issued to OpenID
+/// Connect clients are refused by the [`Claims`] extractor
+pub async fn device_approve(
+    claims: Claims<'_>,
+    Json(payload): Json<DeviceApprovePayload<'_>>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    let user_id = claims.id().ok_or(AuthError::Unauthorized)?;
+
+    let user_code = normalize_user_code(&payload.user_code).ok_or(AuthError::InvalidToken)?;
+    let status = if payload.approve {
+        DeviceCodeStatus::Approved
+    } else {
+        DeviceCodeStatus::Denied
+    };
+
+    state
+        .db
+        .resolve_device_code(user_code, user_id, status)
+        .await?;
+
+    Ok(Json(status))
+}
+
+fn generate_user_code() -> String {
+    let mut rng = ::rand::rng();
+    let code = (0..USER_CODE_LENGTH)
+        .filter_map(|_| USER_CODE_CHARSET.choose(&mut rng).copied().map(char::from))
+        .collect::<String>();
+
+    format_user_code(&code)
+}
+
+/// Accepts user input in any case and with any separators, as RFC 8628 suggests
+fn normalize_user_code(input: &str) -> Option<String> {
+    let code = input
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_uppercase())
+        .collect::<String>();
+
+    if code.len() != USER_CODE_LENGTH {
+        return None;
+    }
+
+    Some(format_user_code(&code))
+}
+
+fn format_user_code(code: &str) -> String {
+    let (left, right) = code.split_at(USER_CODE_LENGTH / 2);
+    format!("{left}-{right}")
+}
@@ -1,12 +1,16 @@
 mod authorize;
+mod certificate;
+mod device;
 mod revoke;
 mod token;
 mod util;
 
 pub use self::{
     authorize::*,
+    certificate::*,
+    device::*,
     revoke::*,
     token::*,
 };
 
-static COOKIE_JWT: &str = "JWT_RT";
\ No newline at end of file
+pub(crate) static COOKIE_JWT: &str = "JWT_RT";
\ No newline at end of file
@@ -1,13 +1,22 @@
 use super::COOKIE_JWT;
-use crate::{app::get_state, middleware::Claims, repository::TokenRepository};
-use ::api_util::{AuthError, Error};
+use crate::{
+    amqp::publish_auth_event,
+    app::get_state,
+    middleware::ClientInfo,
+    repository::{AuthRepository, TokenRepository},
+};
+use ::api_util::{
+    AuthError, Error,
+    auth::Claims,
+    auth_event::{AuthAction, AuthEvent},
+};
 use ::axum::{
     http::header::SET_COOKIE,
     response::{AppendHeaders, IntoResponse},
 };
 use ::axum_extra::extract::CookieJar;
 
-pub async fn revoke(jar: CookieJar) -> Result<impl IntoResponse, Error> {
+pub async fn revoke(client: ClientInfo, jar: CookieJar) -> Result<impl IntoResponse, Error> {
     let cookie = jar.get(COOKIE_JWT).ok_or(AuthError::MissingToken)?;
 
     let state = get_state();
@@ -15,7 +24,17 @@ pub async fn revoke(jar: CookieJar) -> Result<impl IntoResponse, Error> {
     if let Some(refresh_token) =
         Claims::from_refresh_token(cookie.value(), &state.cfg.security.jwt_keys.decoding)?.jti
     {
+        let mut event = AuthEvent::default().with_ip(client.ip_string());
+        if let Ok(auth) = state.db.find_auth_by_token(refresh_token.clone()).await {
+            event = event.with_user_id(auth.id);
+        }
+        if let Ok(session) = state.db.find_session(refresh_token.clone()).await {
+            event = event.with_device(session.device);
+        }
+
         state.db.delete_refresh_token(refresh_token).await?;
+
+        publish_auth_event(AuthAction::Revoke, &event).await;
     }
 
     Ok(AppendHeaders(vec![(
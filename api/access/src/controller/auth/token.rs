@@ -1,14 +1,13 @@
-use super::{COOKIE_JWT, util::build_token_response};
-use crate::{
-    app::get_state,
-    middleware::Claims,
-    repository::{AuthRepository, TokenRepository},
+use super::{
+    COOKIE_JWT,
+    util::{build_token_response, refresh_auth},
 };
-use ::api_util::{AuthError, Error};
+use crate::{app::get_state, middleware::ClientInfo};
+use ::api_util::{AuthError, Error, auth::Claims};
 use ::axum::response::IntoResponse;
 use ::axum_extra::extract::CookieJar;
 
-pub async fn token(jar: CookieJar) -> Result<impl IntoResponse, Error> {
+pub async fn token(client: ClientInfo, jar: CookieJar) -> Result<impl IntoResponse, Error> {
     let cookie = jar.get(COOKIE_JWT).ok_or(AuthError::MissingToken)?;
 
     let state = get_state();
@@ -18,18 +17,8 @@ pub async fn token(jar: CookieJar) -> Result<impl IntoResponse, Error> {
             .jti
             .ok_or(AuthError::MissingToken)?;
 
-    let auth = state
-        .db
-        .find_auth_by_token(current_refresh_token.clone())
-        .await?;
-
-    let refresh_token_uuid = state
-        .db
-        .update_refresh_token(
-            current_refresh_token,
-            state.cfg.security.jwt.refresh_expires_in,
-        )
-        .await?;
+    let (auth, refresh_token_uuid) =
+        refresh_auth(current_refresh_token.into_owned(), &client).await?;
 
     build_token_response(auth, refresh_token_uuid).await
 }
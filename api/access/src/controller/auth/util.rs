@@ -1,11 +1,16 @@
 use super::COOKIE_JWT;
 use crate::{
+    amqp::publish_auth_event,
     app::get_state,
-    middleware::{Auth, Claims},
-    model::Permissions,
-    repository::AuthEntityDto,
+    middleware::ClientInfo,
+    model::{SessionLimitPolicy, SessionPolicy},
+    repository::{AuthEntityDto, AuthRepository, SessionEntity, TokenRepository},
+};
+use ::api_util::{
+    AuthError, Error,
+    auth::{Auth, Claims, Permissions},
+    auth_event::{AuthAction, AuthEvent, SessionLimit},
 };
-use ::api_util::Error;
 use ::axum::{
     Json,
     http::header::SET_COOKIE,
@@ -22,6 +27,146 @@ pub struct TokenBody<'a> {
     pub access_token: Cow<'a, str>,
 }
 
+/// Opens a new session for the user, enforcing the active sessions limit first.
+///
+/// `method` names the way the user proved their identity and is published with the
+/// login event.
+pub async fn create_session(
+    user_id: &str,
+    device: Option<Cow<'_, str>>,
+    client: &ClientInfo,
+    method: &'static str,
+) -> Result<Cow<'static, str>, Error> {
+    let state = get_state();
+
+    let limits = state.db.find_session_limits(user_id).await?;
+    let limit = SessionPolicy::resolve_limit(&limits);
+
+    // Nothing can be evicted to make room when no sessions are allowed at all
+    let policy = match limit {
+        Some(0) => SessionLimitPolicy::Reject,
+        _ => state.cfg.security.session.limit_policy,
+    };
+
+    // Counting, evicting and creating run in one transaction, so concurrent logins
+    // cannot exceed the limit together
+    let session = state
+        .db
+        .create_refresh_token(
+            user_id,
+            state.cfg.security.jwt.refresh_expires_in,
+            device.as_deref(),
+            client,
+            limit,
+            policy == SessionLimitPolicy::Evict,
+        )
+        .await?;
+
+    let limited = session.token.is_none() || !session.evicted.is_empty();
+    if let Some(limit) = limit.filter(|_| limited) {
+        publish_auth_event(
+            AuthAction::SessionLimit,
+            &AuthEvent::default()
+                .with_user_id(user_id)
+                .with_device(device.as_deref())
+                .with_ip(client.ip_string())
+                .with_session_limit(SessionLimit {
+                    limit,
+                    policy: policy.as_str().to_string(),
+                    evicted: session.evicted,
+                }),
+        )
+        .await;
+    }
+
+    let Some(refresh_token_uuid) = session.token else {
+        return Err(AuthError::SessionLimitExceeded.into());
+    };
+
+    let event = AuthEvent::default()
+        .with_user_id(user_id)
+        .with_method(method)
+        .with_device(device.as_deref())
+        .with_ip(client.ip_string());
+    publish_auth_event(AuthAction::Login, &event).await;
+
+    Ok(Cow::Owned(refresh_token_uuid))
+}
+
+/// Publishes a login refused before a session was opened
+pub async fn publish_login_failure(
+    login: Option<&str>,
+    method: &'static str,
+    client: &ClientInfo,
+    err: &Error,
+) {
+    let mut event = AuthEvent::default()
+        .with_method(method)
+        .with_ip(client.ip_string())
+        .with_reason(err.to_string());
+    if let Some(login) = login {
+        event = event.with_login(login);
+    }
+
+    publish_auth_event(AuthAction::LoginFailed, &event).await;
+}
+
+/// Continues the session bound to the refresh token, applying the session policy
+/// before its expiration and client metadata are updated.
+pub async fn refresh_auth(
+    refresh_token: String,
+    client: &ClientInfo,
+) -> Result<(AuthEntityDto<'static>, Cow<'static, str>), Error> {
+    let mut event = AuthEvent::default().with_ip(client.ip_string());
+
+    // The device is known as soon as the session is found, even when it can't be continued
+    let continued = match get_state().db.find_session(refresh_token.clone()).await {
+        Ok(session) => {
+            event = event.with_device(session.device.as_deref());
+            continue_session(session, refresh_token, client).await
+        }
+        Err(err) => Err(err),
+    };
+
+    match continued {
+        Ok((auth, refresh_token_uuid)) => {
+            publish_auth_event(AuthAction::Refresh, &event.with_user_id(auth.id.as_ref())).await;
+            Ok((auth, refresh_token_uuid))
+        }
+        Err(err) => {
+            publish_auth_event(
+                AuthAction::RefreshFailed,
+                &event.with_reason(err.to_string()),
+            )
+            .await;
+            Err(err)
+        }
+    }
+}
+
+async fn continue_session(
+    session: SessionEntity,
+    refresh_token: String,
+    client: &ClientInfo,
+) -> Result<(AuthEntityDto<'static>, Cow<'static, str>), Error> {
+    let state = get_state();
+
+    // The client a session is bound to never changes, only its validity has to be
+    // checked again when the token is extended
+    state.cfg.security.session.verify(&session, client)?;
+
+    let auth = state
+        .db
+        .refresh_auth_by_token(
+            refresh_token.clone(),
+            state.cfg.security.jwt.refresh_expires_in,
+            client,
+        )
+        .await?;
+
+    Ok((auth, Cow::Owned(refresh_token)))
+}
+
 pub async fn build_token_response(
     auth: AuthEntityDto<'_>,
     refresh_token_uuid: Cow<'_, str>,
@@ -0,0 +1,178 @@
+use super::{SCOPE_OPENID, SUPPORTED_SCOPES, has_scope};
+use crate::{
+    app::get_state,
+    controller::auth::COOKIE_JWT,
+    middleware::ClientInfo,
+    repository::{AuthEntityDto, AuthRepository, OidcCodeEntity, OidcRepository, TokenRepository},
+};
+use ::api_util::{AuthError, Error, auth::Claims};
+use ::axum::{
+    extract::{OriginalUri, Query},
+    response::Redirect,
+};
+use ::axum_extra::extract::CookieJar;
+use ::serde::Deserialize;
+use ::std::borrow::Cow;
+use ::url::Url;
+
+static CODE_CHALLENGE_METHOD: &str = "S256";
+
+#[derive(Deserialize)]
+pub struct OidcAuthorizePayload<'a> {
+    pub response_type: Cow<'a, str>,
+    pub client_id: Cow<'a, str>,
+    pub redirect_uri: Cow<'a, str>,
+    #[serde(default)]
+    pub scope: Cow<'a, str>,
+    #[serde(default)]
+    pub state: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub nonce: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub code_challenge: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub code_challenge_method: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub prompt: Option<Cow<'a, str>>,
+}
+
+/// Authorization endpoint of the authorization code flow, PKCE with `S256` is mandatory.
+///
+/// The user is identified by the refresh token cookie of an active u2 session, without
+/// one the user agent is sent to the login page and returns here afterwards.
+pub async fn authorize(
+    client: ClientInfo,
+    jar: CookieJar,
+    OriginalUri(uri): OriginalUri,
+    Query(payload): Query<OidcAuthorizePayload<'_>>,
+) -> Result<Redirect, Error> {
+    let state = get_state();
+
+    // Errors are never redirected to a uri that is not registered for the client
+    let oidc_client = state
+        .db
+        .find_oidc_client(payload.client_id.as_ref())
+        .await?
+        .ok_or(AuthError::InvalidClient)?;
+    if !oidc_client
+        .redirect_uris
+        .iter()
+        .any(|uri| uri == payload.redirect_uri.as_ref())
+    {
+        Err(AuthError::InvalidRequest)?
+    }
+
+    let redirect_uri = payload.redirect_uri.as_ref();
+    let oauth_state = payload.state.as_deref();
+
+    if payload.response_type != "code" {
+        return Ok(redirect_error(
+            redirect_uri,
+            AuthError::UnsupportedResponseType,
+            oauth_state,
+        )?);
+    }
+
+    if !has_scope(&payload.scope, SCOPE_OPENID) {
+        return Ok(redirect_error(
+            redirect_uri,
+            AuthError::InvalidScope,
+            oauth_state,
+        )?);
+    }
+
+    let Some(code_challenge) = payload
+        .code_challenge
+        .as_deref()
+        .filter(|_| payload.code_challenge_method.as_deref() == Some(CODE_CHALLENGE_METHOD))
+    else {
+        return Ok(redirect_error(
+            redirect_uri,
+            AuthError::InvalidRequest,
+            oauth_state,
+        )?);
+    };
+
+    let Ok(auth) = find_session_auth(&jar, &client).await else {
+        if payload.prompt.as_deref() == Some("none") {
+            return Ok(redirect_error(
+                redirect_uri,
+                AuthError::LoginRequired,
+                oauth_state,
+            )?);
+        }
+
+        let return_to = uri.path_and_query().map_or("/", |uri| uri.as_str());
+        return Ok(redirect_with(
+            state.cfg.security.oidc.login_uri,
+            &[("return_to", return_to)],
+        )?);
+    };
+
+    let scope = payload
+        .scope
+        .split_whitespace()
+        .filter(|scope| SUPPORTED_SCOPES.contains(scope))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let code = state
+        .db
+        .create_oidc_code(
+            OidcCodeEntity {
+                client_id: payload.client_id.to_string(),
+                user_id: auth.id.into_owned(),
+                redirect_uri: redirect_uri.to_string(),
+                scope,
+                nonce: payload.nonce.as_deref().map(str::to_string),
+                code_challenge: code_challenge.to_string(),
+            },
+            state.cfg.security.oidc.code_expires_in,
+        )
+        .await?;
+
+    let mut params = vec![("code", code.as_ref())];
+    params.extend(oauth_state.map(|oauth_state| ("state", oauth_state)));
+
+    Ok(redirect_with(redirect_uri, &params)?)
+}
+
+/// Resolves the user of the session bound to the refresh token cookie, the session
+/// itself is left untouched
+async fn find_session_auth(
+    jar: &CookieJar,
+    client: &ClientInfo,
+) -> Result<AuthEntityDto<'static>, Error> {
+    let state = get_state();
+    let cookie = jar.get(COOKIE_JWT).ok_or(AuthError::MissingToken)?;
+
+    let refresh_token =
+        Claims::from_refresh_token(cookie.value(), &state.cfg.security.jwt_keys.decoding)?
+            .jti
+            .ok_or(AuthError::MissingToken)?
+            .into_owned();
+
+    let session = state.db.find_session(refresh_token.clone()).await?;
+    state.cfg.security.session.verify(&session, client)?;
+
+    state.db.find_auth_by_token(refresh_token).await
+}
+
+fn redirect_error(
+    redirect_uri: &str,
+    error: AuthError,
+    oauth_state: Option<&str>,
+) -> Result<Redirect, AuthError> {
+    let error = error.to_string();
+    let mut params = vec![("error", error.as_str())];
+    params.extend(oauth_state.map(|oauth_state| ("state", oauth_state)));
+
+    redirect_with(redirect_uri, &params)
+}
+
+fn redirect_with(uri: &str, params: &[(&str, &str)]) -> Result<Redirect, AuthError> {
+    let mut url = Url::parse(uri).map_err(|_| AuthError::InvalidRequest)?;
+    url.query_pairs_mut().extend_pairs(params);
+
+    Ok(Redirect::to(url.as_str()))
+}
@@ -0,0 +1,88 @@
+use crate::{app::get_state, repository::OidcRepository};
+use ::api_util::{
+    AuthError, Error,
+    auth::{ACCESS_PERMISSION, Capabilities, Claims},
+};
+use ::axum::{Json, extract::Path, http::StatusCode, response::IntoResponse};
+use ::base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ::rand::RngCore;
+use ::serde::{Deserialize, Serialize};
+use ::std::borrow::Cow;
+use ::url::Url;
+
+const CLIENT_SECRET_LENGTH: usize = 32;
+
+#[derive(Deserialize)]
+pub struct OidcClientPayload<'a> {
+    pub name: Cow<'a, str>,
+    pub redirect_uris: Vec<String>,
+    #[serde(default)]
+    pub public: bool,
+}
+
+#[derive(Serialize)]
+pub struct OidcClientBody<'a> {
+    pub client_id: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub redirect_uris: Vec<String>,
+}
+
+/// Registers a relying party, the client secret is only ever returned in this response
+pub async fn create_client(
+    claims: Claims<'_>,
+    Json(payload): Json<OidcClientPayload<'_>>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    claims.has_capabilities(ACCESS_PERMISSION, Capabilities::CREATE)?;
+    let user_id = claims.id().ok_or(AuthError::Unauthorized)?;
+
+    if payload.redirect_uris.is_empty()
+        || payload
+            .redirect_uris
+            .iter()
+            .any(|uri| Url::parse(uri).is_err())
+    {
+        Err(AuthError::InvalidRequest)?
+    }
+
+    let client_secret = (!payload.public).then(generate_client_secret);
+
+    let client_id = state
+        .db
+        .create_oidc_client(
+            payload.name,
+            client_secret.clone(),
+            payload.redirect_uris.clone(),
+            user_id,
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OidcClientBody {
+            client_id,
+            client_secret,
+            redirect_uris: payload.redirect_uris,
+        }),
+    ))
+}
+
+pub async fn delete_client(
+    claims: Claims<'_>,
+    Path(client_id): Path<String>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    claims.has_capabilities(ACCESS_PERMISSION, Capabilities::DELETE)?;
+
+    state.db.delete_oidc_client(client_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn generate_client_secret() -> String {
+    let mut secret = [0u8; CLIENT_SECRET_LENGTH];
+    ::rand::rng().fill_bytes(&mut secret);
+
+    URL_SAFE_NO_PAD.encode(secret)
+}
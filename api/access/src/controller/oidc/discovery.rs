@@ -0,0 +1,59 @@
+use super::{AUTHORIZATION_PATH, JWKS_PATH, SUPPORTED_SCOPES, TOKEN_PATH, USERINFO_PATH};
+use crate::app::get_state;
+use ::axum::{Json, response::IntoResponse};
+use ::serde::Serialize;
+
+#[derive(Serialize)]
+pub struct DiscoveryBody<'a> {
+    pub issuer: &'a str,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub scopes_supported: &'a [&'a str],
+    pub response_types_supported: &'a [&'a str],
+    pub grant_types_supported: &'a [&'a str],
+    pub subject_types_supported: &'a [&'a str],
+    pub id_token_signing_alg_values_supported: &'a [&'a str],
+    pub token_endpoint_auth_methods_supported: &'a [&'a str],
+    pub code_challenge_methods_supported: &'a [&'a str],
+    pub claims_supported: &'a [&'a str],
+}
+
+pub async fn discovery() -> impl IntoResponse {
+    let issuer = get_state().cfg.security.oidc.issuer;
+
+    Json(DiscoveryBody {
+        issuer,
+        authorization_endpoint: format!("{issuer}{AUTHORIZATION_PATH}"),
+        token_endpoint: format!("{issuer}{TOKEN_PATH}"),
+        userinfo_endpoint: format!("{issuer}{USERINFO_PATH}"),
+        jwks_uri: format!("{issuer}{JWKS_PATH}"),
+        scopes_supported: SUPPORTED_SCOPES,
+        response_types_supported: &["code"],
+        grant_types_supported: &["authorization_code"],
+        subject_types_supported: &["public"],
+        id_token_signing_alg_values_supported: &["ES256"],
+        token_endpoint_auth_methods_supported: &[
+            "client_secret_basic",
+            "client_secret_post",
+            "none",
+        ],
+        code_challenge_methods_supported: &["S256"],
+        claims_supported: &[
+            "iss",
+            "sub",
+            "aud",
+            "iat",
+            "exp",
+            "nonce",
+            "preferred_username",
+            "groups",
+        ],
+    })
+}
+
+/// Public key of the ID token signatures
+pub async fn jwks() -> impl IntoResponse {
+    Json(&get_state().cfg.security.oidc.id_token_keys.jwks)
+}
@@ -0,0 +1,26 @@
+mod authorize;
+mod client;
+mod discovery;
+mod token;
+mod userinfo;
+
+pub use self::{
+    authorize::*,
+    client::*,
+    discovery::*,
+    token::*,
+    userinfo::*,
+};
+
+static AUTHORIZATION_PATH: &str = "/api/auth/oidc/authorize";
+static TOKEN_PATH: &str = "/api/auth/oidc/token";
+static USERINFO_PATH: &str = "/api/auth/oidc/userinfo";
+static JWKS_PATH: &str = "/api/auth/oidc/jwks";
+
+static SCOPE_OPENID: &str = "openid";
+static SCOPE_PROFILE: &str = "profile";
+static SUPPORTED_SCOPES: &[&str] = &[SCOPE_OPENID, SCOPE_PROFILE];
+
+fn has_scope(scope: &str, expected: &str) -> bool {
+    scope.split_whitespace().any(|scope| scope == expected)
+}
\ No newline at end of file
@@ -0,0 +1,153 @@
+use super::{SCOPE_PROFILE, has_scope};
+use crate::{
+    app::get_state,
+    middleware::{CLIENT_TOKEN_AUDIENCE, IdToken},
+    repository::{AuthRepository, OidcRepository},
+};
+use ::api_util::{
+    AuthError, Error,
+    auth::{Auth, Claims, Permissions},
+};
+use ::axum::{
+    Form, Json,
+    http::header::{CACHE_CONTROL, PRAGMA},
+    response::{AppendHeaders, IntoResponse},
+};
+use ::axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Basic},
+};
+use ::base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ::serde::{Deserialize, Serialize};
+use ::sha2::{Digest, Sha256};
+use ::std::{borrow::Cow, collections::HashMap};
+
+/// RFC 7636 bounds of the code verifier length
+const CODE_VERIFIER_LENGTH: std::ops::RangeInclusive<usize> = 43..=128;
+
+#[derive(Deserialize)]
+pub struct OidcTokenPayload<'a> {
+    pub grant_type: Cow<'a, str>,
+    #[serde(default)]
+    pub code: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub redirect_uri: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub client_id: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub client_secret: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub code_verifier: Option<Cow<'a, str>>,
+}
+
+#[derive(Serialize)]
+pub struct OidcTokenBody<'a> {
+    pub access_token: String,
+    pub token_type: &'a str,
+    pub expires_in: i64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// Token endpoint of the authorization code flow, clients authenticate with
+/// `client_secret_basic`, `client_secret_post` or, when public, PKCE only
+pub async fn token(
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(payload): Form<OidcTokenPayload<'_>>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+
+    if payload.grant_type != "authorization_code" {
+        Err(AuthError::UnsupportedGrantType)?
+    }
+
+    let (client_id, client_secret) = match &basic {
+        Some(TypedHeader(Authorization(basic))) => (basic.username(), Some(basic.password())),
+        None => (
+            payload
+                .client_id
+                .as_deref()
+                .ok_or(AuthError::InvalidClient)?,
+            payload.client_secret.as_deref(),
+        ),
+    };
+
+    let oidc_client = state
+        .db
+        .find_oidc_client(client_id)
+        .await?
+        .ok_or(AuthError::InvalidClient)?;
+    if oidc_client.confidential {
+        let client_secret = client_secret.ok_or(AuthError::InvalidClient)?;
+        state
+            .db
+            .authenticate_oidc_client(client_id, client_secret)
+            .await?;
+    }
+
+    // Authorization codes are single use, the code is consumed before it is verified
+    let code = payload.code.ok_or(AuthError::InvalidRequest)?;
+    let code = state
+        .db
+        .consume_oidc_code(code)
+        .await?
+        .ok_or(AuthError::InvalidGrant)?;
+
+    if code.client_id != client_id
+        || payload.redirect_uri.as_deref() != Some(code.redirect_uri.as_str())
+    {
+        Err(AuthError::InvalidGrant)?
+    }
+
+    let code_verifier = payload.code_verifier.ok_or(AuthError::InvalidGrant)?;
+    if !verify_code_challenge(&code_verifier, &code.code_challenge) {
+        Err(AuthError::InvalidGrant)?
+    }
+
+    // Rejects users blocked since the code was issued
+    let auth = state.db.find_auth_by_id(code.user_id.as_str()).await?;
+
+    // Access tokens issued to clients identify the user only, u2 capabilities are never delegated
+    // and their audience keeps them out of the first-party API
+    let access_token = Claims::new()
+        .with_issuer(state.cfg.security.jwt.issuer)
+        .with_subject(state.cfg.security.jwt.subject)
+        .with_audience(CLIENT_TOKEN_AUDIENCE)
+        .with_expiration_in_seconds(state.cfg.security.jwt.access_expires_in)
+        .with_auth(Auth {
+            id: auth.id,
+            permissions: Permissions::init(&state.permissions_map.read().await, HashMap::new()),
+        })
+        .build_token(&state.cfg.security.jwt_keys.encoding)?;
+
+    let mut id_token = IdToken::new(
+        state.cfg.security.oidc.issuer,
+        code.user_id.as_str(),
+        client_id,
+        state.cfg.security.oidc.id_token_expires_in,
+    )
+    .with_nonce(code.nonce.as_deref());
+
+    if has_scope(&code.scope, SCOPE_PROFILE) {
+        let userinfo = state.db.find_userinfo(code.user_id.as_str()).await?;
+        id_token = id_token.with_profile(userinfo.preferred_username, userinfo.groups);
+    }
+
+    let id_token = id_token.build_token(&state.cfg.security.oidc.id_token_keys)?;
+
+    Ok((
+        AppendHeaders([(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")]),
+        Json(OidcTokenBody {
+            access_token,
+            token_type: "Bearer",
+            expires_in: state.cfg.security.jwt.access_expires_in,
+            id_token,
+            scope: code.scope,
+        }),
+    ))
+}
+
+fn verify_code_challenge(code_verifier: &str, code_challenge: &str) -> bool {
+    CODE_VERIFIER_LENGTH.contains(&code_verifier.len())
+        && URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())) == code_challenge
+}
@@ -0,0 +1,12 @@
+use crate::{app::get_state, middleware::ClientClaims, repository::OidcRepository};
+use ::api_util::{AuthError, Error};
+use ::axum::{Json, response::IntoResponse};
+
+pub async fn userinfo(ClientClaims(claims): ClientClaims<'_>) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    let user_id = claims.id().ok_or(AuthError::Unauthorized)?;
+
+    let userinfo = state.db.find_userinfo(user_id).await?;
+
+    Ok(Json(userinfo))
+}
@@ -8,7 +8,7 @@ mod repository;
 use crate::{
     amqp::init_amqp,
     app::{get_state, init_app, init_state},
-    repository::TokenRepository,
+    repository::{DeviceRepository, OidcRepository, TokenRepository},
 };
 use ::api_util::{Error, console::*, log, panic::*, server, shutdown::*};
 use ::tokio::time::{Duration, sleep};
@@ -36,12 +36,18 @@ async fn main() -> Result<(), Box<Error>> {
             if state.db.delete_expired_refresh_tokens().await.is_ok() {
                 log::info!("expired refresh tokens deleted successfully");
             };
+            if state.db.delete_expired_device_codes().await.is_ok() {
+                log::info!("expired device codes deleted successfully");
+            };
+            if state.db.delete_expired_oidc_codes().await.is_ok() {
+                log::info!("expired authorization codes deleted successfully");
+            };
             sleep(timeout).await;
         }
     });
 
     server::start_server(init_app(), shutdown_handle).await;
 
-    print_service_stopped();
+    print_service_stopped().await;
     Ok(())
 }
@@ -0,0 +1,43 @@
+use crate::app::get_state;
+use ::api_util::{AuthError, Error, auth::Claims};
+use ::axum::{RequestPartsExt, extract::FromRequestParts, http::request::Parts};
+use ::axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use ::jsonwebtoken::{Validation, decode};
+
+/// Audience of the access tokens issued to OpenID Connect clients
+pub const CLIENT_TOKEN_AUDIENCE: &str = "oidc-client";
+
+/// Access token issued to an OpenID Connect client.
+///
+/// It identifies the user only and is refused by the first-party [`Claims`] extractor, so
+/// a client can never call the API on behalf of the user.
+pub struct ClientClaims<'a>(pub Claims<'a>);
+
+impl<S> FromRequestParts<S> for ClientClaims<'_>
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AuthError::MissingToken)?;
+
+        let mut validation = Validation::default();
+        validation.set_audience(&[CLIENT_TOKEN_AUDIENCE]);
+
+        let token_data = decode::<Claims>(
+            bearer.token(),
+            &get_state().cfg.security.jwt_keys.decoding,
+            &validation,
+        )
+        .map_err(|_| AuthError::InvalidToken)?;
+
+        Ok(Self(token_data.claims))
+    }
+}
@@ -0,0 +1,115 @@
+use crate::app::get_state;
+use ::api_util::{Error, log::warn};
+use ::axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, header::USER_AGENT, request::Parts},
+};
+use ::base64::{Engine, engine::general_purpose::STANDARD};
+use ::std::net::{IpAddr, SocketAddr};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_CLIENT_CERT_SUBJECT: &str = "x-client-cert-subject";
+const X_CLIENT_CERT_SERIAL: &str = "x-client-cert-serial";
+
+/// Client certificate verified by the TLS proxy
+pub struct ClientCertificate {
+    pub subject: String,
+    pub serial: String,
+}
+
+#[derive(Default)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub certificate: Option<ClientCertificate>,
+}
+
+impl ClientInfo {
+    pub fn ip_string(&self) -> Option<String> {
+        self.ip.map(|ip| ip.to_string())
+    }
+
+    fn is_trusted_proxy(ip: &IpAddr) -> bool {
+        get_state()
+            .cfg
+            .security
+            .trusted_proxies
+            .iter()
+            .any(|net| net.contains(ip))
+    }
+
+    /// Resolves the client address behind a trusted proxy. The `X-Forwarded-For`
+    /// chain is walked from the right and the first untrusted hop is taken as the client.
+    fn resolve_forwarded_ip(peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let forwarded = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|value| value.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !Self::is_trusted_proxy(ip))
+            .or(forwarded.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+
+    /// The proxy base64 encodes the subject, distinguished names may hold any UTF-8
+    fn resolve_certificate(headers: &HeaderMap) -> Option<ClientCertificate> {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+        let subject = header(X_CLIENT_CERT_SUBJECT)?;
+        let Some(subject) = STANDARD
+            .decode(subject)
+            .ok()
+            .and_then(|subject| String::from_utf8(subject).ok())
+        else {
+            warn!("'{X_CLIENT_CERT_SUBJECT}' header ignored, not base64 encoded UTF-8");
+            return None;
+        };
+
+        Some(ClientCertificate {
+            subject,
+            serial: header(X_CLIENT_CERT_SERIAL)?.to_owned(),
+        })
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        // Forwarding headers are only honoured when set by a trusted proxy
+        let (ip, certificate) = match peer {
+            Some(peer) if Self::is_trusted_proxy(&peer) => (
+                Some(Self::resolve_forwarded_ip(peer, &parts.headers)),
+                Self::resolve_certificate(&parts.headers),
+            ),
+            peer => (peer, None),
+        };
+
+        Ok(Self {
+            ip,
+            user_agent,
+            certificate,
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use super::IdTokenKeys;
+use ::api_util::AuthError;
+use ::chrono::Utc;
+use ::jsonwebtoken::{Algorithm, Header, encode};
+use ::serde::Serialize;
+use ::std::borrow::Cow;
+
+/// OpenID Connect ID token, signed with the ES256 key of [`IdTokenKeys`] so that relying
+/// parties verify it with the published JWK set and hold no secret of the service.
+#[derive(Serialize)]
+pub struct IdToken<'a> {
+    pub iss: Cow<'a, str>,
+    pub sub: Cow<'a, str>,
+    pub aud: Cow<'a, str>,
+    pub iat: usize,
+    pub exp: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<String>>,
+}
+
+impl<'a> IdToken<'a> {
+    pub fn new(
+        iss: impl Into<Cow<'a, str>>,
+        sub: impl Into<Cow<'a, str>>,
+        aud: impl Into<Cow<'a, str>>,
+        expires_in: i64,
+    ) -> Self {
+        let timestamp_now = Utc::now().timestamp();
+        Self {
+            iss: iss.into(),
+            sub: sub.into(),
+            aud: aud.into(),
+            iat: timestamp_now as usize,
+            exp: (timestamp_now + expires_in) as usize,
+            nonce: None,
+            preferred_username: None,
+            groups: None,
+        }
+    }
+
+    pub fn with_nonce(mut self, nonce: Option<impl Into<Cow<'a, str>>>) -> Self {
+        self.nonce = nonce.map(Into::into);
+        self
+    }
+
+    pub fn with_profile(
+        mut self,
+        preferred_username: impl Into<Cow<'a, str>>,
+        groups: Vec<String>,
+    ) -> Self {
+        self.preferred_username = Some(preferred_username.into());
+        self.groups = Some(groups);
+        self
+    }
+
+    pub fn build_token(&self, keys: &IdTokenKeys) -> Result<String, AuthError> {
+        let header = Header {
+            kid: Some(keys.kid.clone()),
+            ..Header::new(Algorithm::ES256)
+        };
+
+        encode(&header, self, &keys.encoding).map_err(|_| AuthError::TokenCreation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdToken, IdTokenKeys};
+    use ::jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+    use ::serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Subject {
+        sub: String,
+    }
+
+    #[test]
+    fn verifies_with_the_published_key() {
+        let keys = IdTokenKeys::new(None);
+        let token = IdToken::new("https://localhost", "users:alice", "client", 600)
+            .build_token(&keys)
+            .unwrap();
+
+        let kid = decode_header(&token).unwrap().kid.unwrap();
+        let jwk = keys.jwks.find(&kid).unwrap();
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.set_audience(&["client"]);
+        let claims = decode::<Subject>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+            .unwrap()
+            .claims;
+
+        assert_eq!(claims.sub, "users:alice");
+    }
+}
@@ -0,0 +1,85 @@
+use ::api_util::log::warn;
+use ::base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use ::jsonwebtoken::{
+    EncodingKey,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+        EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse,
+    },
+};
+use ::ring::{
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use ::sha2::{Digest, Sha256};
+use ::std::fs;
+
+/// ES256 key signing ID tokens, relying parties verify them with the public half
+/// published as a JWK set
+pub struct IdTokenKeys {
+    pub encoding: EncodingKey,
+    pub kid: String,
+    pub jwks: JwkSet,
+}
+
+impl IdTokenKeys {
+    /// Loads the PKCS#8 PEM encoded P-256 key at `path`. Without one a key is generated,
+    /// tokens it signs can't be verified once the service restarts or by other replicas.
+    pub fn new(path: Option<&str>) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = match path {
+            Some(path) => {
+                let pem = fs::read_to_string(path).expect("failed to read the ID token key");
+                let body = pem
+                    .lines()
+                    .filter(|line| !line.starts_with("-----"))
+                    .collect::<String>();
+                STANDARD
+                    .decode(body)
+                    .expect("ID token key is not PEM encoded")
+            }
+            None => {
+                warn!("no ID token key configured, signing with a generated one");
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                    .expect("failed to generate the ID token key")
+                    .as_ref()
+                    .to_vec()
+            }
+        };
+
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+            .expect("ID token key is not a PKCS#8 P-256 key");
+        // Uncompressed point, a tag byte followed by both coordinates
+        let (x, y) = key_pair.public_key().as_ref()[1..].split_at(32);
+        let (x, y) = (URL_SAFE_NO_PAD.encode(x), URL_SAFE_NO_PAD.encode(y));
+
+        // RFC 7638 thumbprint, members in lexicographic order
+        let kid = URL_SAFE_NO_PAD.encode(Sha256::digest(format!(
+            r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#
+        )));
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::ES256),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x,
+                y,
+            }),
+        };
+
+        Self {
+            encoding: EncodingKey::from_ec_der(&pkcs8),
+            kid,
+            jwks: JwkSet { keys: vec![jwk] },
+        }
+    }
+}
@@ -1,9 +1,15 @@
-mod auth;
+mod client_claims;
+mod client_info;
+mod id_token;
+mod id_token_keys;
 mod jwt_keys;
 mod claims;
 
 pub use self::{
-    auth::*,
+    client_claims::*,
+    client_info::*,
+    id_token::*,
+    id_token_keys::*,
     jwt_keys::*,
     claims::*,
-};
\ No newline at end of file
+};
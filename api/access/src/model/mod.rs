@@ -1,7 +1,5 @@
-mod capabilities;
-mod permissions;
+mod session_policy;
 
 pub use self::{
-    capabilities::*,
-    permissions::*,
+    session_policy::*,
 };
\ No newline at end of file
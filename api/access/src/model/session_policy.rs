@@ -0,0 +1,99 @@
+use crate::{middleware::ClientInfo, repository::SessionEntity};
+use ::api_util::AuthError;
+use ::ipnet::IpNet;
+use ::std::{net::IpAddr, str::FromStr};
+
+/// Action taken when a login would exceed the active sessions limit
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SessionLimitPolicy {
+    /// Revoke the oldest sessions to make room for the new one
+    Evict,
+    /// Refuse the new login
+    Reject,
+}
+
+impl SessionLimitPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Evict => "evict",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+impl FromStr for SessionLimitPolicy {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "evict" => Ok(Self::Evict),
+            "reject" => Ok(Self::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Rules applied when a refresh token is used, comparing the current client
+/// against the one that created the session.
+///
+/// A prefix length of `0` disables the IP check for that address family.
+pub struct SessionPolicy {
+    pub ipv4_prefix: u8,
+    pub ipv6_prefix: u8,
+    pub bind_user_agent: bool,
+    pub limit_policy: SessionLimitPolicy,
+}
+
+impl SessionPolicy {
+    /// Verifies that the client is allowed to continue the session
+    pub fn verify(&self, session: &SessionEntity, client: &ClientInfo) -> Result<(), AuthError> {
+        if !self.is_same_network(session.ip.as_deref(), client.ip) {
+            return Err(AuthError::SessionMismatch);
+        }
+
+        if self.bind_user_agent
+            && session.user_agent.is_some()
+            && session.user_agent != client.user_agent
+        {
+            return Err(AuthError::SessionMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the active sessions limit of a user from the limits of its groups.
+    ///
+    /// The most permissive group wins, and a group without a limit lifts it entirely.
+    pub fn resolve_limit(limits: &[Option<u32>]) -> Option<u32> {
+        if limits.is_empty() {
+            return None;
+        }
+
+        limits
+            .iter()
+            .try_fold(0, |max, limit| limit.map(|limit| max.max(limit)))
+    }
+
+    /// Checks if both addresses belong to the same configured network range
+    fn is_same_network(&self, origin: Option<&str>, current: Option<IpAddr>) -> bool {
+        let Some(origin) = origin.and_then(|ip| ip.parse::<IpAddr>().ok()) else {
+            return true;
+        };
+
+        let prefix = match origin {
+            IpAddr::V4(_) => self.ipv4_prefix,
+            IpAddr::V6(_) => self.ipv6_prefix,
+        };
+        if prefix == 0 {
+            return true;
+        }
+
+        let Some(current) = current else {
+            return false;
+        };
+
+        IpNet::new(origin, prefix)
+            .map(|net| net.trunc().contains(&current))
+            .unwrap_or(false)
+    }
+}
@@ -1,4 +1,6 @@
+use crate::middleware::ClientInfo;
 use ::api_util::{AuthError, Error};
+use ::chrono::Utc;
 use ::serde::{Deserialize, Serialize};
 use ::std::{borrow::Cow, collections::HashMap};
 use ::surrealdb::{Surreal, engine::remote::ws::Client};
@@ -19,9 +21,21 @@ pub trait AuthRepository {
         &self,
         login: impl Into<String>,
         password: impl Into<String>,
-    ) -> Result<AuthEntityDto, Error>;
+    ) -> Result<AuthEntityDto<'_>, Error>;
     async fn find_auth_by_token(&self, refresh_token: impl Into<String>)
-    -> Result<AuthEntityDto, Error>;
+    -> Result<AuthEntityDto<'_>, Error>;
+    async fn refresh_auth_by_token(
+        &self,
+        refresh_token: impl Into<String>,
+        expiration: i64,
+        client: &ClientInfo,
+    ) -> Result<AuthEntityDto<'_>, Error>;
+    async fn find_auth_by_id(&self, user_id: impl Into<String>) -> Result<AuthEntityDto<'_>, Error>;
+    async fn find_auth_by_certificate(
+        &self,
+        subject: impl Into<String>,
+        serial: impl Into<String>,
+    ) -> Result<AuthEntityDto<'_>, Error>;
 }
 
 impl AuthRepository for Surreal<Client> {
@@ -29,7 +43,7 @@ impl AuthRepository for Surreal<Client> {
         &self,
         login: impl Into<String>,
         password: impl Into<String>,
-    ) -> Result<AuthEntityDto, Error> {
+    ) -> Result<AuthEntityDto<'_>, Error> {
         #[derive(Serialize)]
         struct SqlParams {
             login: String,
@@ -54,7 +68,7 @@ impl AuthRepository for Surreal<Client> {
     async fn find_auth_by_token(
         &self,
         refresh_token: impl Into<String>,
-    ) -> Result<AuthEntityDto, Error> {
+    ) -> Result<AuthEntityDto<'_>, Error> {
         let user = self.query(include_str!(
             "../../res/query/middleware/auth/by_token.surql"
         ))
@@ -65,6 +79,76 @@ impl AuthRepository for Surreal<Client> {
         
         Ok(entity_to_dto(user))
     }
+
+    /// Extends a refresh token still valid and records the client using it, returning the
+    /// auth of its user in the same transaction
+    async fn refresh_auth_by_token(
+        &self,
+        refresh_token: impl Into<String>,
+        expiration: i64,
+        client: &ClientInfo,
+    ) -> Result<AuthEntityDto<'_>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            token_id: String,
+            expiration_at: i64,
+            ip: Option<String>,
+            user_agent: Option<String>,
+        }
+
+        let user = self
+            .query(include_str!(
+                "../../res/query/middleware/auth/refresh.surql"
+            ))
+            .bind(SqlParams {
+                token_id: refresh_token.into(),
+                expiration_at: Utc::now().timestamp() + expiration,
+                ip: client.ip_string(),
+                user_agent: client.user_agent.clone(),
+            })
+            .await?
+            .take::<Option<AuthEntity>>(0)?
+            .ok_or(AuthError::InvalidToken)?;
+
+        Ok(entity_to_dto(user))
+    }
+
+    async fn find_auth_by_id(&self, user_id: impl Into<String>) -> Result<AuthEntityDto<'_>, Error> {
+        let user = self
+            .query(include_str!("../../res/query/middleware/auth/by_id.surql"))
+            .bind(("user_id", user_id.into()))
+            .await?
+            .take::<Option<AuthEntity>>(0)?
+            .ok_or(AuthError::WrongCredentials)?;
+
+        Ok(entity_to_dto(user))
+    }
+
+    async fn find_auth_by_certificate(
+        &self,
+        subject: impl Into<String>,
+        serial: impl Into<String>,
+    ) -> Result<AuthEntityDto<'_>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            subject: String,
+            serial: String,
+        }
+
+        let user = self
+            .query(include_str!(
+                "../../res/query/middleware/auth/by_certificate.surql"
+            ))
+            .bind(SqlParams {
+                subject: subject.into(),
+                serial: serial.into(),
+            })
+            .await?
+            .take::<Option<AuthEntity>>(0)?
+            .ok_or(AuthError::WrongCredentials)?;
+
+        Ok(entity_to_dto(user))
+    }
 }
 
 fn entity_to_dto(auth: AuthEntity) -> AuthEntityDto {
@@ -0,0 +1,194 @@
+use ::api_util::{AuthError, Error};
+use ::chrono::Utc;
+use ::serde::{Deserialize, Serialize};
+use ::std::borrow::Cow;
+use ::surrealdb::{Surreal, engine::remote::ws::Client};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceCodeStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceCodeEntity {
+    pub status: DeviceCodeStatus,
+    pub interval: i64,
+    pub last_poll_at: Option<i64>,
+    pub expiration_at: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ApprovedDeviceCodeEntity {
+    pub user_id: String,
+    pub device: Option<String>,
+}
+
+pub trait DeviceRepository {
+    async fn create_device_code(
+        &self,
+        user_code: impl Into<String>,
+        device: Option<impl Into<String>>,
+        interval: i64,
+        expiration: i64,
+    ) -> Result<Cow<'_, str>, Error>;
+    async fn find_device_code(
+        &self,
+        device_code: impl Into<String>,
+    ) -> Result<Option<DeviceCodeEntity>, Error>;
+    async fn update_device_code_poll(
+        &self,
+        device_code: impl Into<String>,
+        interval: i64,
+    ) -> Result<(), Error>;
+    async fn consume_device_code(
+        &self,
+        device_code: impl Into<String>,
+    ) -> Result<Option<ApprovedDeviceCodeEntity>, Error>;
+    async fn resolve_device_code(
+        &self,
+        user_code: impl Into<String>,
+        user_id: impl Into<String>,
+        status: DeviceCodeStatus,
+    ) -> Result<(), Error>;
+    async fn delete_device_code(&self, device_code: impl Into<String>) -> Result<(), Error>;
+    async fn delete_expired_device_codes(&self) -> Result<(), Error>;
+}
+
+impl DeviceRepository for Surreal<Client> {
+    async fn create_device_code(
+        &self,
+        user_code: impl Into<String>,
+        device: Option<impl Into<String>>,
+        interval: i64,
+        expiration: i64,
+    ) -> Result<Cow<'_, str>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            user_code: String,
+            device: Option<String>,
+            interval: i64,
+            expiration_at: i64,
+        }
+
+        self.query(include_str!(
+            "../../res/query/middleware/device/create.surql"
+        ))
+        .bind(SqlParams {
+            user_code: user_code.into(),
+            device: device.map(Into::into),
+            interval,
+            expiration_at: Utc::now().timestamp() + expiration,
+        })
+        .await?
+        .take::<Option<Cow<str>>>(0)?
+        .ok_or(Error::from(AuthError::TokenCreation))
+    }
+
+    async fn find_device_code(
+        &self,
+        device_code: impl Into<String>,
+    ) -> Result<Option<DeviceCodeEntity>, Error> {
+        let device_code = self
+            .query(include_str!("../../res/query/middleware/device/find.surql"))
+            .bind(("device_code", device_code.into()))
+            .await?
+            .take::<Option<DeviceCodeEntity>>(0)?;
+
+        Ok(device_code)
+    }
+
+    async fn update_device_code_poll(
+        &self,
+        device_code: impl Into<String>,
+        interval: i64,
+    ) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            device_code: String,
+            interval: i64,
+        }
+
+        self.query(include_str!("../../res/query/middleware/device/poll.surql"))
+            .bind(SqlParams {
+                device_code: device_code.into(),
+                interval,
+            })
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Deletes an approved device code, `None` when it is not approved or was consumed
+    /// by a concurrent poll
+    async fn consume_device_code(
+        &self,
+        device_code: impl Into<String>,
+    ) -> Result<Option<ApprovedDeviceCodeEntity>, Error> {
+        let approved = self
+            .query(include_str!(
+                "../../res/query/middleware/device/consume.surql"
+            ))
+            .bind(("device_code", device_code.into()))
+            .await?
+            .take::<Option<ApprovedDeviceCodeEntity>>(0)?;
+
+        Ok(approved)
+    }
+
+    async fn resolve_device_code(
+        &self,
+        user_code: impl Into<String>,
+        user_id: impl Into<String>,
+        status: DeviceCodeStatus,
+    ) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            user_code: String,
+            user_id: String,
+            status: DeviceCodeStatus,
+        }
+
+        let resolved = self
+            .query(include_str!(
+                "../../res/query/middleware/device/approve.surql"
+            ))
+            .bind(SqlParams {
+                user_code: user_code.into(),
+                user_id: user_id.into(),
+                status,
+            })
+            .await?
+            .take::<Vec<String>>(0)?;
+
+        if resolved.is_empty() {
+            Err(AuthError::InvalidToken)?
+        }
+
+        Ok(())
+    }
+
+    async fn delete_device_code(&self, device_code: impl Into<String>) -> Result<(), Error> {
+        self.query(include_str!(
+            "../../res/query/middleware/device/delete.surql"
+        ))
+        .bind(("device_code", device_code.into()))
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_device_codes(&self) -> Result<(), Error> {
+        self.query(include_str!(
+            "../../res/query/middleware/device/delete_expired.surql"
+        ))
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+}
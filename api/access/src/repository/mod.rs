@@ -1,9 +1,13 @@
 mod permissions;
 mod token;
 mod auth;
+mod device;
+mod oidc;
 
 pub use self::{
     permissions::*,
     token::*,
     auth::*,
-};
\ No newline at end of file
+    device::*,
+    oidc::*,
+};
@@ -0,0 +1,203 @@
+use ::api_util::{AuthError, Error};
+use ::chrono::Utc;
+use ::serde::{Deserialize, Serialize};
+use ::std::borrow::Cow;
+use ::surrealdb::{Surreal, engine::remote::ws::Client};
+
+#[derive(Deserialize)]
+pub struct OidcClientEntity {
+    pub redirect_uris: Vec<String>,
+    pub confidential: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OidcCodeEntity {
+    pub client_id: String,
+    pub user_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UserInfoEntity {
+    pub sub: String,
+    pub preferred_username: String,
+    pub groups: Vec<String>,
+}
+
+pub trait OidcRepository {
+    async fn create_oidc_client(
+        &self,
+        name: impl Into<String>,
+        secret: Option<impl Into<String>>,
+        redirect_uris: Vec<String>,
+        user_id: impl Into<String>,
+    ) -> Result<Cow<'_, str>, Error>;
+    async fn find_oidc_client(
+        &self,
+        client_id: impl Into<String>,
+    ) -> Result<Option<OidcClientEntity>, Error>;
+    async fn authenticate_oidc_client(
+        &self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Result<(), Error>;
+    async fn delete_oidc_client(&self, client_id: impl Into<String>) -> Result<(), Error>;
+    async fn create_oidc_code(
+        &self,
+        code: OidcCodeEntity,
+        expiration: i64,
+    ) -> Result<Cow<'_, str>, Error>;
+    async fn consume_oidc_code(
+        &self,
+        code: impl Into<String>,
+    ) -> Result<Option<OidcCodeEntity>, Error>;
+    async fn delete_expired_oidc_codes(&self) -> Result<(), Error>;
+    async fn find_userinfo(&self, user_id: impl Into<String>) -> Result<UserInfoEntity, Error>;
+}
+
+impl OidcRepository for Surreal<Client> {
+    async fn create_oidc_client(
+        &self,
+        name: impl Into<String>,
+        secret: Option<impl Into<String>>,
+        redirect_uris: Vec<String>,
+        user_id: impl Into<String>,
+    ) -> Result<Cow<'_, str>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            name: String,
+            secret: Option<String>,
+            redirect_uris: Vec<String>,
+            user_id: String,
+        }
+
+        self.query(include_str!(
+            "../../res/query/middleware/oidc/client_create.surql"
+        ))
+        .bind(SqlParams {
+            name: name.into(),
+            secret: secret.map(Into::into),
+            redirect_uris,
+            user_id: user_id.into(),
+        })
+        .await?
+        .take::<Option<Cow<str>>>(0)?
+        .ok_or(Error::from(AuthError::InvalidRequest))
+    }
+
+    async fn find_oidc_client(
+        &self,
+        client_id: impl Into<String>,
+    ) -> Result<Option<OidcClientEntity>, Error> {
+        let client = self
+            .query(include_str!(
+                "../../res/query/middleware/oidc/client_find.surql"
+            ))
+            .bind(("client_id", client_id.into()))
+            .await?
+            .take::<Option<OidcClientEntity>>(0)?;
+
+        Ok(client)
+    }
+
+    async fn authenticate_oidc_client(
+        &self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            client_id: String,
+            client_secret: String,
+        }
+
+        self.query(include_str!(
+            "../../res/query/middleware/oidc/client_authenticate.surql"
+        ))
+        .bind(SqlParams {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+        })
+        .await?
+        .take::<Option<String>>(0)?
+        .ok_or(AuthError::InvalidClient)?;
+
+        Ok(())
+    }
+
+    async fn delete_oidc_client(&self, client_id: impl Into<String>) -> Result<(), Error> {
+        self.query(include_str!(
+            "../../res/query/middleware/oidc/client_delete.surql"
+        ))
+        .bind(("client_id", client_id.into()))
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+
+    async fn create_oidc_code(
+        &self,
+        code: OidcCodeEntity,
+        expiration: i64,
+    ) -> Result<Cow<'_, str>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            code: OidcCodeEntity,
+            expiration_at: i64,
+        }
+
+        self.query(include_str!(
+            "../../res/query/middleware/oidc/code_create.surql"
+        ))
+        .bind(SqlParams {
+            code,
+            expiration_at: Utc::now().timestamp() + expiration,
+        })
+        .await?
+        .take::<Option<Cow<str>>>(0)?
+        .ok_or(Error::from(AuthError::TokenCreation))
+    }
+
+    async fn consume_oidc_code(
+        &self,
+        code: impl Into<String>,
+    ) -> Result<Option<OidcCodeEntity>, Error> {
+        let code = self
+            .query(include_str!(
+                "../../res/query/middleware/oidc/code_consume.surql"
+            ))
+            .bind(("code", code.into()))
+            .await?
+            .take::<Option<OidcCodeEntity>>(0)?;
+
+        Ok(code)
+    }
+
+    async fn delete_expired_oidc_codes(&self) -> Result<(), Error> {
+        self.query(include_str!(
+            "../../res/query/middleware/oidc/code_delete_expired.surql"
+        ))
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+
+    async fn find_userinfo(&self, user_id: impl Into<String>) -> Result<UserInfoEntity, Error> {
+        let userinfo = self
+            .query(include_str!(
+                "../../res/query/middleware/oidc/userinfo.surql"
+            ))
+            .bind(("user_id", user_id.into()))
+            .await?
+            .take::<Option<UserInfoEntity>>(0)?
+            .ok_or(AuthError::InvalidToken)?;
+
+        Ok(userinfo)
+    }
+}
@@ -1,21 +1,36 @@
-use std::borrow::Cow;
+use crate::middleware::ClientInfo;
 use ::api_util::{AuthError, Error};
 use ::chrono::Utc;
-use ::serde::Serialize;
+use ::serde::{Deserialize, Serialize};
 use ::surrealdb::{Surreal, engine::remote::ws::Client};
 
+#[derive(Deserialize)]
+pub struct SessionEntity {
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatedSessionEntity {
+    /// `None` when the limit was reached and nothing could be evicted
+    pub token: Option<String>,
+    pub evicted: Vec<String>,
+}
+
 pub trait TokenRepository {
     async fn create_refresh_token(
         &self,
         user_id: impl Into<String>,
         expiration: i64,
         device: Option<impl Into<String>>,
-    ) -> Result<Cow<str>, Error>;
-    async fn update_refresh_token(
-        &self,
-        id: impl Into<String>,
-        expiration: i64,
-    ) -> Result<Cow<str>, Error>;
+        client: &ClientInfo,
+        limit: Option<u32>,
+        evict: bool,
+    ) -> Result<CreatedSessionEntity, Error>;
+    async fn find_session(&self, id: impl Into<String>) -> Result<SessionEntity, Error>;
+    async fn find_session_limits(&self, user_id: impl Into<String>)
+    -> Result<Vec<Option<u32>>, Error>;
     async fn delete_refresh_token(&self, id: impl Into<String>) -> Result<(), Error>;
     async fn delete_expired_refresh_tokens(&self) -> Result<(), Error>;
 }
@@ -26,12 +41,19 @@ impl TokenRepository for Surreal<Client> {
         user_id: impl Into<String>,
         expiration: i64,
         device: Option<impl Into<String>>,
-    ) -> Result<Cow<str>, Error> {
+        client: &ClientInfo,
+        limit: Option<u32>,
+        evict: bool,
+    ) -> Result<CreatedSessionEntity, Error> {
         #[derive(Serialize)]
         struct SqlParams {
             user_id: String,
             expiration_at: i64,
             device: Option<String>,
+            ip: Option<String>,
+            user_agent: Option<String>,
+            limit: Option<u32>,
+            evict: bool,
         }
 
         self.query(include_str!(
@@ -41,35 +63,39 @@ impl TokenRepository for Surreal<Client> {
             user_id: user_id.into(),
             expiration_at: Utc::now().timestamp() + expiration,
             device: device.map(Into::into),
+            ip: client.ip_string(),
+            user_agent: client.user_agent.clone(),
+            limit,
+            evict,
         })
         .await?
-        .take::<Option<Cow<str>>>(0)?
+        .take::<Option<CreatedSessionEntity>>(0)?
         .ok_or(Error::from(AuthError::TokenCreation))
     }
 
-    async fn update_refresh_token(
-        &self,
-        id: impl Into<String>,
-        expiration: i64,
-    ) -> Result<Cow<str>, Error> {
-        #[derive(Serialize)]
-        struct SqlParams {
-            token_id: String,
-            expiration_at: i64,
-        }
-
+    async fn find_session(&self, id: impl Into<String>) -> Result<SessionEntity, Error> {
         self.query(include_str!(
-            "../../res/query/middleware/token/update.surql"
+            "../../res/query/middleware/token/session.surql"
         ))
-        .bind(SqlParams {
-            token_id: id.into(),
-            expiration_at: Utc::now().timestamp() + expiration,
-        })
+        .bind(("token_id", id.into()))
         .await?
-        .take::<Option<Cow<str>>>(0)?
+        .take::<Option<SessionEntity>>(0)?
         .ok_or(Error::from(AuthError::InvalidToken))
     }
 
+    async fn find_session_limits(
+        &self,
+        user_id: impl Into<String>,
+    ) -> Result<Vec<Option<u32>>, Error> {
+        Ok(self
+            .query(include_str!(
+                "../../res/query/middleware/token/limits.surql"
+            ))
+            .bind(("user_id", user_id.into()))
+            .await?
+            .take::<Vec<Option<u32>>>(0)?)
+    }
+
     async fn delete_refresh_token(&self, id: impl Into<String>) -> Result<(), Error> {
         self.query(include_str!(
             "../../res/query/middleware/token/delete.surql"
@@ -0,0 +1,102 @@
+use super::{REQUEUE_DELAY, non_empty, park};
+use crate::{
+    app::get_state,
+    model::{AuditAction, redact},
+    repository::AuditEventEntity,
+};
+use ::api_util::{
+    Error,
+    amqp::{Delivery, DeliveryExt, DeliveryResult},
+    auth_event::AuthAction,
+    log,
+};
+use ::chrono::Utc;
+use ::serde_json::{Map, Value};
+
+/// Stores `auth.*` deliveries of the access service before acknowledging them.
+///
+/// Deliveries that can never be stored are parked, a failing database requeues them after
+/// a delay.
+pub async fn consumer(delivery: DeliveryResult) {
+    let Ok(Some(delivery)) = delivery else { return };
+
+    match store(&delivery).await {
+        Ok(()) => delivery.confirm(),
+        Err(err) => {
+            log::error!(
+                "'{}' storing auth event: {err}",
+                delivery.routing_key.as_str()
+            );
+            delivery.requeue_after(REQUEUE_DELAY)
+        }
+    }
+}
+
+async fn store(delivery: &Delivery) -> Result<(), Error> {
+    let state = get_state();
+
+    let Some(action) = AuthAction::from_routing_key(delivery.routing_key.as_str()) else {
+        return park(delivery, "unsupported routing key".to_string()).await;
+    };
+
+    let message_id = delivery.message_id();
+    if message_id.is_empty() {
+        return park(delivery, "missing message id".to_string()).await;
+    }
+
+    let mut payload = match delivery.extract_json::<Map<String, Value>>() {
+        Ok(payload) => payload,
+        Err(err) => return park(delivery, err.to_string()).await,
+    };
+
+    let user_id = take_string(&mut payload, "user_id");
+    let occurred_at = payload
+        .remove("occurred_at")
+        .and_then(|occurred_at| occurred_at.as_i64())
+        .unwrap_or_else(|| Utc::now().timestamp());
+
+    // Refused logins have no user, the attempted login identifies them instead
+    let (entity_type, entity_id) = match user_id.clone().or_else(|| string(&payload, "login")) {
+        Some(id) => ("user", id),
+        None => ("service", delivery.app_id().to_string()),
+    };
+
+    let mut payload = Value::Object(payload);
+    redact(&mut payload, &state.cfg.sensitive_fields);
+    let action = AuditAction::from(action);
+
+    state
+        .append_audit_event(AuditEventEntity {
+            message_id: message_id.to_string(),
+            seq: 0,
+            prev_hash: String::new(),
+            hash: String::new(),
+            service: delivery.app_id().to_string(),
+            actor: user_id,
+            entity_type: entity_type.to_string(),
+            entity_id,
+            category: action.category(),
+            action,
+            occurred_at,
+            changed_fields: Vec::new(),
+            payload: match payload {
+                Value::Object(payload) if !payload.is_empty() => Some(payload),
+                _ => None,
+            },
+            correlation_id: non_empty(delivery.correlation_id()),
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn take_string(payload: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match payload.remove(key)? {
+        Value::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn string(payload: &Map<String, Value>, key: &str) -> Option<String> {
+    payload.get(key)?.as_str().map(ToOwned::to_owned)
+}
@@ -1,14 +1,81 @@
-use ::api_util::amqp::{DeliveryExt, DeliveryResult};
+use super::{REQUEUE_DELAY, non_empty, park};
+use crate::{
+    app::get_state,
+    model::{AuditAction, entity_payload},
+    repository::AuditEventEntity,
+};
+use ::api_util::{
+    Error,
+    amqp::{Delivery, DeliveryExt, DeliveryResult},
+    entity_event::EntityAction,
+    log,
+};
+use ::serde_json::Value;
 
+/// Stores `entity.*` deliveries before acknowledging them.
+///
+/// Deliveries that can never be stored are parked, a failing database requeues them after
+/// a delay.
 pub async fn consumer(delivery: DeliveryResult) {
     let Ok(Some(delivery)) = delivery else { return };
 
-    match delivery.routing_key.as_str() {
-        "entity.created" => {}
-        "entity.updated" => {}
-        "entity.deleted" => {}
-        _ => (),
+    match store(&delivery).await {
+        Ok(()) => delivery.confirm(),
+        Err(err) => {
+            log::error!(
+                "'{}' storing audit event: {err}",
+                delivery.routing_key.as_str()
+            );
+            delivery.requeue_after(REQUEUE_DELAY)
+        }
     }
+}
+
+async fn store(delivery: &Delivery) -> Result<(), Error> {
+    let state = get_state();
+
+    if EntityAction::from_routing_key(delivery.routing_key.as_str()).is_none() {
+        return park(delivery, "unsupported routing key".to_string()).await;
+    }
+
+    let message_id = delivery.message_id();
+    if message_id.is_empty() {
+        return park(delivery, "missing message id".to_string()).await;
+    }
+
+    let event = match delivery.extract_entity_event::<Value>() {
+        Ok(event) => event,
+        Err(err) => return park(delivery, err.to_string()).await,
+    };
+
+    let (payload, changed_fields) = entity_payload(
+        event.action,
+        event.before,
+        event.after,
+        &state.cfg.sensitive_fields,
+    );
+    let action = AuditAction::from(event.action);
+
+    state
+        .append_audit_event(AuditEventEntity {
+            message_id: message_id.to_string(),
+            seq: 0,
+            prev_hash: String::new(),
+            hash: String::new(),
+            service: delivery.app_id().to_string(),
+            actor: event.actor,
+            entity_type: event.entity.kind,
+            entity_id: event.entity.id,
+            category: action.category(),
+            action,
+            occurred_at: event.occurred_at,
+            changed_fields,
+            payload,
+            correlation_id: event
+                .correlation_id
+                .or_else(|| non_empty(delivery.correlation_id())),
+        })
+        .await?;
 
-    delivery.confirm()
+    Ok(())
 }
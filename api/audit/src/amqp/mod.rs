@@ -1,10 +1,20 @@
+mod auth;
+mod broadcast;
 mod entity;
 
-use crate::app::get_state;
+use crate::{
+    app::get_state,
+    repository::{EventRepository, ParkedEventEntity},
+};
 use ::api_util::{
     Error,
-    amqp::{AMQPChannelOptions, ExchangeKind},
+    amqp::{AMQPChannelOptions, Delivery, DeliveryExt, ExchangeKind},
+    log,
 };
+use ::std::time::Duration;
+
+/// Delay before a delivery that failed to be stored is retried
+const REQUEUE_DELAY: Duration = Duration::from_secs(5);
 
 pub async fn init_amqp() -> Result<(), Error> {
     let state = get_state();
@@ -21,5 +31,55 @@ pub async fn init_amqp() -> Result<(), Error> {
         )
         .await?;
 
+    // Failure outcomes nest one level deeper, e.g. `auth.login.failed`
+    state
+        .amqp
+        .set_delegate(
+            "audit.auth",
+            AMQPChannelOptions::default()
+                .with_exchange(ExchangeKind::Topic)
+                .with_routing_key("auth.#")
+                .with_durable(),
+            auth::consumer,
+        )
+        .await?;
+
+    state
+        .amqp
+        .set_delegate(
+            "audit.broadcast",
+            AMQPChannelOptions::default()
+                .with_exchange(ExchangeKind::Fanout)
+                .with_durable(),
+            broadcast::consumer,
+        )
+        .await?;
+
     Ok(())
 }
+
+/// Keeps a delivery that can never be stored for inspection, so it can be acknowledged
+async fn park(delivery: &Delivery, reason: String) -> Result<(), Error> {
+    let state = get_state();
+
+    log::warn!(
+        "'{}' parking event: {reason}",
+        delivery.routing_key.as_str()
+    );
+
+    state
+        .db
+        .park_event(ParkedEventEntity {
+            message_id: non_empty(delivery.message_id()),
+            routing_key: delivery.routing_key.to_string(),
+            service: non_empty(delivery.app_id()),
+            correlation_id: non_empty(delivery.correlation_id()),
+            reason,
+            data: delivery.extract_string(),
+        })
+        .await
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
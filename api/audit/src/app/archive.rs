@@ -0,0 +1,170 @@
+use super::{config::Retention, state::AppState};
+use crate::{
+    model::{ArchiveManifest, ExpiredFilter, GENESIS_HASH},
+    repository::{ArchiveRepository, ChainLinkEntity, LeaseRepository},
+};
+use ::api_util::Error;
+use ::chrono::Utc;
+use ::flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use ::sha2::{Digest, Sha256};
+use ::std::io::{BufRead, BufReader, Write};
+use ::tokio::fs;
+
+const ARCHIVE_BATCH_SIZE: u32 = 10_000;
+const ARCHIVE_LEASE: &str = "archive";
+/// Outlives any run, a replica dying mid-run only blocks archiving for this long
+const ARCHIVE_LEASE_SECONDS: u64 = 3_600;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+impl AppState {
+    /// Moves the content of records past their retention period into gzip compressed
+    /// JSON Lines files, returns the number of archived records.
+    ///
+    /// Archived records are kept as tombstones. Their row stays in the chain with its
+    /// `seq`, `prev_hash`, `hash`, metadata and the name of its archive, while the actor,
+    /// payload, changed fields and correlation id only remain in the archive file. The
+    /// chain can still be walked and every archive checked against its manifest.
+    ///
+    /// Runs are serialised across replicas by a lease, archive sequence numbers are taken
+    /// from the last archive while it is held.
+    pub async fn archive_expired(&self) -> Result<usize, Error> {
+        if !self
+            .db
+            .acquire_lease(ARCHIVE_LEASE, &self.instance, ARCHIVE_LEASE_SECONDS)
+            .await?
+        {
+            return Ok(0);
+        }
+
+        let archived = self.archive_batches().await;
+        self.db.release_lease(ARCHIVE_LEASE, &self.instance).await?;
+
+        archived
+    }
+
+    async fn archive_batches(&self) -> Result<usize, Error> {
+        let mut archived = 0;
+
+        for filter in expired_filters(&self.cfg.retention, Utc::now().timestamp()) {
+            loop {
+                let events = self
+                    .db
+                    .find_expired_events(filter.clone(), ARCHIVE_BATCH_SIZE)
+                    .await?;
+                let batch_len = events.len();
+
+                if batch_len > 0 {
+                    self.write_archive(events).await?;
+                    archived += batch_len;
+                }
+
+                if batch_len < ARCHIVE_BATCH_SIZE as usize {
+                    break;
+                }
+            }
+        }
+
+        Ok(archived)
+    }
+
+    /// Writes the archive and its manifest next to each other, the records are stripped
+    /// only once both are on disk
+    async fn write_archive(&self, events: Vec<ChainLinkEntity>) -> Result<(), Error> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(());
+        };
+
+        let last_archive = self.db.find_last_archive().await?;
+        let seq = last_archive.as_ref().map_or(1, |manifest| manifest.seq + 1);
+        let name = format!("audit-{seq:08}");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for event in &events {
+            serde_json::to_writer(&mut encoder, event)?;
+            encoder.write_all(b"\n")?;
+        }
+        let data = encoder.finish()?;
+
+        let mut manifest = ArchiveManifest {
+            file: format!("{name}.jsonl.gz"),
+            name,
+            seq,
+            checksum: hex::encode(Sha256::digest(&data)),
+            records: events.len(),
+            first_seq: first.seq,
+            last_seq: last.seq,
+            last_hash: last.hash.clone(),
+            created_at: Utc::now().timestamp(),
+            prev_hash: last_archive.map_or(GENESIS_HASH.to_string(), |manifest| manifest.hash),
+            hash: String::new(),
+        };
+        manifest.hash = manifest.compute_hash();
+
+        let path = &self.cfg.retention.archive_path;
+        fs::create_dir_all(path).await?;
+        fs::write(path.join(&manifest.file), &data).await?;
+        fs::write(
+            path.join(format!("{}.manifest.json", manifest.name)),
+            serde_json::to_vec_pretty(&manifest)?,
+        )
+        .await?;
+
+        let ids = events.into_iter().map(|event| event.id).collect();
+        self.db.create_archive(manifest, ids).await
+    }
+
+    /// Checks an archive file against its manifest and the hash of every record in it
+    pub async fn verify_archive(
+        &self,
+        manifest: &ArchiveManifest,
+    ) -> Result<Option<&'static str>, Error> {
+        let Ok(data) = fs::read(self.cfg.retention.archive_path.join(&manifest.file)).await else {
+            return Ok(Some("missing archive file"));
+        };
+
+        if hex::encode(Sha256::digest(&data)) != manifest.checksum {
+            return Ok(Some("archive checksum mismatch"));
+        }
+
+        let mut records = 0;
+        for line in BufReader::new(GzDecoder::new(data.as_slice())).lines() {
+            let link = serde_json::from_str::<ChainLinkEntity>(&line?)?;
+            if link.chain_content().hash(&link.prev_hash) != link.hash {
+                return Ok(Some("archived content hash mismatch"));
+            }
+            records += 1;
+        }
+
+        if records != manifest.records {
+            return Ok(Some("archived record count mismatch"));
+        }
+
+        Ok(None)
+    }
+}
+
+/// One filter per entity type with its own retention and one for every other type
+fn expired_filters(retention: &Retention, now: i64) -> Vec<ExpiredFilter> {
+    let before = |days: u64| now - days as i64 * SECONDS_PER_DAY;
+
+    let mut filters = retention
+        .entity_days
+        .iter()
+        .filter(|(_, days)| **days > 0)
+        .map(|(entity_type, days)| ExpiredFilter {
+            entity_type: Some(*entity_type),
+            excluded: Vec::new(),
+            before: before(*days),
+        })
+        .collect::<Vec<_>>();
+
+    if retention.default_days > 0 {
+        filters.push(ExpiredFilter {
+            entity_type: None,
+            excluded: retention.entity_days.keys().copied().collect(),
+            before: before(retention.default_days),
+        });
+    }
+
+    filters
+}
@@ -1,13 +1,134 @@
+use ::api_util::env;
+use ::std::{collections::HashMap, path::PathBuf};
+
 pub struct AppConfig {
     pub name: &'static str,
     pub version: &'static str,
+    pub chain: Chain,
+    pub retention: Retention,
+    pub feed: Feed,
+    pub report: Report,
+    /// Entity fields whose values are never stored, compared case-insensitively
+    pub sensitive_fields: Vec<&'static str>,
+}
+
+pub struct Chain {
+    /// Required, the service refuses to start without it
+    pub checkpoint_secret: &'static str,
+    pub checkpoint_interval: u64,
+}
+
+/// Retention periods in days, `0` keeps records forever
+pub struct Retention {
+    pub default_days: u64,
+    pub entity_days: HashMap<&'static str, u64>,
+    pub archive_interval: u64,
+    pub archive_path: PathBuf,
+}
+
+/// Change feeds of the audited tables, read from the database they live in
+pub struct Feed {
+    pub tables: Vec<&'static str>,
+    pub interval: u64,
+    pub database: &'static str,
+    pub user: &'static str,
+    pub password: &'static str,
 }
 
-impl  AppConfig {
+/// Periodic aggregate reports of the stored records
+pub struct Report {
+    pub interval: u64,
+    /// Failed logins within one hour from which the hour is reported as a spike
+    pub spike_threshold: u64,
+    pub top_entities: u32,
+    /// Audited entity types whose changes alter what users are allowed to do
+    pub permission_types: Vec<&'static str>,
+}
+
+impl AppConfig {
     pub fn new() -> Self {
+        let chain = Chain {
+            checkpoint_secret: env::get_var_or_default("AUDIT_CHECKPOINT_SECRET", ""),
+            checkpoint_interval: env::get_var_or_default("AUDIT_CHECKPOINT_INTERVAL", "3600")
+                .parse()
+                .unwrap_or(3600),
+        };
+
+        let retention = Retention {
+            default_days: env::get_var_or_default("AUDIT_RETENTION_DAYS", "0")
+                .parse()
+                .unwrap_or(0),
+            entity_days: env::get_var_or_default("AUDIT_ENTITY_RETENTION_DAYS", "")
+                .split(',')
+                .filter_map(|rule| rule.split_once('='))
+                .filter_map(|(entity_type, days)| {
+                    Some((entity_type.trim(), days.trim().parse().ok()?))
+                })
+                .collect(),
+            archive_interval: env::get_var_or_default("AUDIT_ARCHIVE_INTERVAL", "86400")
+                .parse()
+                .unwrap_or(86400),
+            archive_path: PathBuf::from(env::get_var_or_default("DATA_PATH", "/etc/u2"))
+                .join("archive/"),
+        };
+
+        let feed = Feed {
+            tables: env::get_var_or_default(
+                "AUDIT_FEED_TABLES",
+                "users,groups,permissions,rel_group_permissions,rel_user_groups",
+            )
+            .split(',')
+            .map(str::trim)
+            .filter(|table| {
+                !table.is_empty()
+                    && table
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || char == '_')
+            })
+            .collect(),
+            interval: env::get_var_or_default("AUDIT_FEED_INTERVAL", "5")
+                .parse()
+                .unwrap_or(5),
+            database: env::get_var_or_default("AUDIT_FEED_DB_NAME", "core"),
+            user: env::get_var_or_default("AUDIT_FEED_DB_USER", "root"),
+            password: env::get_var_or_default("AUDIT_FEED_DB_PASS", "root"),
+        };
+
+        let report = Report {
+            interval: env::get_var_or_default("AUDIT_REPORT_INTERVAL", "3600")
+                .parse()
+                .unwrap_or(3600),
+            spike_threshold: env::get_var_or_default("AUDIT_REPORT_SPIKE_THRESHOLD", "10")
+                .parse()
+                .unwrap_or(10),
+            top_entities: env::get_var_or_default("AUDIT_REPORT_TOP_ENTITIES", "10")
+                .parse()
+                .unwrap_or(10),
+            permission_types: env::get_var_or_default(
+                "AUDIT_REPORT_PERMISSION_TYPES",
+                "permissions,rel_group_permissions,rel_user_groups",
+            )
+            .split(',')
+            .map(str::trim)
+            .filter(|entity_type| !entity_type.is_empty())
+            .collect(),
+        };
+
         Self {
             name: env!("CARGO_PKG_NAME"),
-            version: env!("CARGO_PKG_VERSION"),            
+            version: env!("CARGO_PKG_VERSION"),
+            chain,
+            retention,
+            feed,
+            report,
+            sensitive_fields: env::get_var_or_default(
+                "AUDIT_SENSITIVE_FIELDS",
+                "password,password_hash,secret,client_secret,totp_secret",
+            )
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect(),
         }
     }
-}
\ No newline at end of file
+}
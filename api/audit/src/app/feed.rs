@@ -0,0 +1,235 @@
+use super::state::AppState;
+use crate::{
+    model::{AuditAction, FeedChange, entity_payload},
+    repository::{AuditEventEntity, ChangeFeedRepository},
+};
+use ::api_util::Error;
+use ::chrono::Utc;
+use ::serde_json::{Map, Value};
+use ::surrealdb::{Connection, Surreal};
+
+const FEED_BATCH_SIZE: u32 = 100;
+const FEED_SERVICE: &str = "changefeed";
+
+impl AppState {
+    /// Turns the changes of every audited table since its persisted cursor into audit
+    /// records, returns the number of stored records
+    pub async fn tail_change_feeds(&self) -> Result<usize, Error> {
+        let mut stored = 0;
+
+        for table in &self.cfg.feed.tables {
+            stored += tail_change_feed(
+                &self.feed_db,
+                &self.db,
+                self,
+                table,
+                &self.cfg.sensitive_fields,
+            )
+            .await?;
+        }
+
+        Ok(stored)
+    }
+}
+
+/// Destination of the audit records captured from change feeds
+pub trait FeedSink {
+    /// Returns whether the record was stored, records already stored are ignored
+    async fn append(&self, event: AuditEventEntity) -> Result<bool, Error>;
+}
+
+impl FeedSink for AppState {
+    async fn append(&self, event: AuditEventEntity) -> Result<bool, Error> {
+        self.append_audit_event(event).await
+    }
+}
+
+/// Hands the changes of one table since its cursor, kept in `db`, to `sink` and returns
+/// the number of stored records
+pub async fn tail_change_feed<C: Connection>(
+    feed_db: &Surreal<C>,
+    db: &Surreal<C>,
+    sink: &impl FeedSink,
+    table: &str,
+    sensitive_fields: &[&str],
+) -> Result<usize, Error> {
+    let mut stored = 0;
+    let mut since = db.find_feed_cursor(table).await?.unwrap_or(0);
+
+    loop {
+        let change_sets = feed_db.find_changes(table, since, FEED_BATCH_SIZE).await?;
+        let batch_len = change_sets.len();
+
+        for change_set in change_sets {
+            let next_since = change_set.next_since();
+            let events = feed_events(
+                table,
+                change_set.versionstamp,
+                change_set.changes,
+                sensitive_fields,
+            );
+            for event in events {
+                if sink.append(event).await? {
+                    stored += 1;
+                }
+            }
+
+            // The cursor only moves past change sets stored in full
+            since = next_since;
+            db.save_feed_cursor(table, since).await?;
+        }
+
+        if batch_len < FEED_BATCH_SIZE as usize {
+            break;
+        }
+    }
+
+    Ok(stored)
+}
+
+/// Audit records of one change set. Record ids derive from the versionstamp, so a change
+/// set read again after a crash is stored once.
+pub fn feed_events(
+    table: &str,
+    versionstamp: i64,
+    changes: Vec<Map<String, Value>>,
+    sensitive_fields: &[&str],
+) -> Vec<AuditEventEntity> {
+    changes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, change)| {
+            let change = FeedChange::parse(table, change)?;
+
+            let (payload, changed_fields) =
+                entity_payload(change.action, change.before, change.after, sensitive_fields);
+            let action = AuditAction::from(change.action);
+
+            Some(AuditEventEntity {
+                message_id: format!("feed-{table}-{versionstamp}-{index}"),
+                seq: 0,
+                prev_hash: String::new(),
+                hash: String::new(),
+                actor: change.actor,
+                service: FEED_SERVICE.to_string(),
+                entity_type: table.to_string(),
+                entity_id: change.entity_id,
+                category: action.category(),
+                action,
+                occurred_at: change.occurred_at.unwrap_or_else(|| Utc::now().timestamp()),
+                changed_fields,
+                payload,
+                correlation_id: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeedSink, tail_change_feed};
+    use crate::repository::{AuditEventEntity, ChangeFeedRepository, EventRepository};
+    use ::api_util::Error;
+    use ::std::sync::atomic::{AtomicI64, Ordering};
+    use ::surrealdb::{
+        Surreal,
+        engine::local::{Db, Mem},
+    };
+
+    const MIGRATIONS: [&str; 6] = [
+        include_str!("../../res/assets/migrations/00001_audit_events.up.surql"),
+        include_str!("../../res/assets/migrations/00002_audit_chain.up.surql"),
+        include_str!("../../res/assets/migrations/00003_audit_changes.up.surql"),
+        include_str!("../../res/assets/migrations/00005_feed_cursors.up.surql"),
+        include_str!("../../res/assets/migrations/00006_audit_categories.up.surql"),
+        include_str!("../../../access/res/assets/migrations/00006_change_feeds.up.surql"),
+    ];
+
+    async fn database() -> Surreal<Db> {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        for migration in MIGRATIONS {
+            db.query(migration).await.unwrap().check().unwrap();
+        }
+        db.query("DEFINE FIELD name ON TABLE users TYPE string;")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        db
+    }
+
+    async fn change(db: &Surreal<Db>, query: &str) {
+        db.query(query).await.unwrap().check().unwrap();
+    }
+
+    /// Links records as the chain would, one sequence at a time
+    struct Chain<'a> {
+        db: &'a Surreal<Db>,
+        seq: AtomicI64,
+    }
+
+    impl FeedSink for Chain<'_> {
+        async fn append(&self, mut event: AuditEventEntity) -> Result<bool, Error> {
+            event.seq = self.seq.fetch_add(1, Ordering::Relaxed) + 1;
+            self.db.create_audit_event(event).await
+        }
+    }
+
+    async fn tail(db: &Surreal<Db>, chain: &Chain<'_>) -> usize {
+        tail_change_feed(db, db, chain, "users", &[]).await.unwrap()
+    }
+
+    async fn actions(db: &Surreal<Db>) -> Vec<(String, Vec<String>)> {
+        db.query("(SELECT * FROM audit_events ORDER BY seq).map(|$event| [$event.action, $event.changed_fields]);")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn tails_every_change_once() {
+        let db = database().await;
+        let chain = Chain {
+            db: &db,
+            seq: AtomicI64::new(0),
+        };
+
+        change(&db, "CREATE users:alice SET name = 'alice';").await;
+        change(&db, "UPDATE users:alice SET name = 'alicia';").await;
+        assert_eq!(tail(&db, &chain).await, 2);
+
+        // The cursor moved past both changes
+        assert_eq!(tail(&db, &chain).await, 0);
+
+        // Change sets read again after losing the cursor are stored once
+        db.save_feed_cursor("users", 0).await.unwrap();
+        assert_eq!(tail(&db, &chain).await, 0);
+
+        change(&db, "DELETE users:alice;").await;
+        assert_eq!(tail(&db, &chain).await, 1);
+
+        assert_eq!(
+            actions(&db).await,
+            [
+                ("created".to_string(), vec![]),
+                ("updated".to_string(), vec!["name".to_string()]),
+                ("deleted".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_table_names_that_are_not_identifiers() {
+        let db = database().await;
+
+        assert!(
+            db.find_changes("users; REMOVE TABLE users", 0, 1)
+                .await
+                .is_err()
+        );
+        assert!(db.find_changes("users", 0, 1).await.is_ok());
+    }
+}
@@ -1,3 +1,6 @@
+mod archive;
+mod feed;
+mod report;
 mod router;
 mod state;
 mod config;
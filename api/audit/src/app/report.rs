@@ -0,0 +1,145 @@
+use super::state::AppState;
+use crate::{
+    model::{AuditReport, FailedLoginSpike, LoginSummary, ReportPeriod, ReportRange, UnitLogins},
+    repository::{ActorLogins, ReportRepository},
+};
+use ::api_util::{
+    Error,
+    amqp::{AMQPMessageOptions, AMQPPoolExt},
+};
+use ::chrono::Utc;
+use ::std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
+
+const PERMISSION_CHANGES_LIMIT: u32 = 500;
+/// Unit of users who belong to no group
+const UNASSIGNED_UNIT: &str = "unassigned";
+
+impl AppState {
+    /// Builds the reports of the last complete day and week unless they already exist and
+    /// delivers them under `report.*`, returns the number of generated reports
+    pub async fn generate_reports(&self) -> Result<usize, Error> {
+        let now = Utc::now().timestamp();
+        let mut generated = 0;
+
+        for period in ReportPeriod::ALL {
+            let range = period.last_complete(now);
+            if self
+                .db
+                .find_report(AuditReport::id(period, &range))
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+
+            let report = self.build_report(period, range).await?;
+
+            // Delivered before it is stored, a failed delivery is retried with the next run
+            // and the report id lets subscribers drop duplicates
+            self.amqp
+                .send_json(
+                    period.routing_key(),
+                    AMQPMessageOptions::default()
+                        .with_app_id(self.cfg.name)
+                        .with_message_id(&report.id)
+                        .with_content_type("application/json")
+                        .with_delivery_mode(2),
+                    &report,
+                )
+                .await?;
+
+            if self.db.create_report(report).await? {
+                generated += 1;
+            }
+        }
+
+        Ok(generated)
+    }
+
+    async fn build_report(
+        &self,
+        period: ReportPeriod,
+        range: ReportRange,
+    ) -> Result<AuditReport, Error> {
+        let cfg = &self.cfg.report;
+
+        let logins = self.db.find_logins_by_actor(range).await?;
+        let mut failed_logins = self.db.find_failed_logins_by_hour(range).await?;
+        failed_logins.sort_unstable_by_key(|failed| failed.hour);
+
+        let permission_changes = self
+            .db
+            .find_permission_changes(
+                range,
+                cfg.permission_types
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                PERMISSION_CHANGES_LIMIT,
+            )
+            .await?;
+        let top_entities = self.db.find_top_entities(range, cfg.top_entities).await?;
+
+        Ok(AuditReport {
+            id: AuditReport::id(period, &range),
+            period,
+            range,
+            generated_at: Utc::now().timestamp(),
+            logins: LoginSummary {
+                total: logins.iter().map(|actor| actor.logins).sum(),
+                failed: failed_logins.iter().map(|failed| failed.failed).sum(),
+                units: self.logins_per_unit(logins).await?,
+            },
+            failed_login_spikes: failed_logins
+                .into_iter()
+                .filter(|failed| failed.failed >= cfg.spike_threshold)
+                .map(|failed| FailedLoginSpike {
+                    hour: failed.hour,
+                    failed: failed.failed,
+                })
+                .collect(),
+            permission_changes,
+            top_entities,
+        })
+    }
+
+    /// Units are the groups of the access service, a user in several groups counts
+    /// towards each of them
+    async fn logins_per_unit(&self, logins: Vec<ActorLogins>) -> Result<Vec<UnitLogins>, Error> {
+        let user_units = self
+            .feed_db
+            .find_user_units(logins.iter().map(|actor| actor.actor.clone()).collect())
+            .await?
+            .into_iter()
+            .map(|user| (user.user_id, user.units))
+            .collect::<HashMap<_, _>>();
+
+        let mut units = BTreeMap::<&str, UnitLogins>::new();
+        for actor in &logins {
+            let names = user_units
+                .get(&actor.actor)
+                .filter(|names| !names.is_empty())
+                .map_or(vec![UNASSIGNED_UNIT], |names| {
+                    names.iter().map(String::as_str).collect()
+                });
+
+            for name in names {
+                let unit = units.entry(name).or_insert_with(|| UnitLogins {
+                    unit: name.to_string(),
+                    logins: 0,
+                    users: 0,
+                });
+                unit.logins += actor.logins;
+                unit.users += 1;
+            }
+        }
+
+        let mut units = units.into_values().collect::<Vec<_>>();
+        units.sort_by_key(|unit| Reverse(unit.logins));
+
+        Ok(units)
+    }
+}
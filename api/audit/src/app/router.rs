@@ -1,10 +1,16 @@
-use ::api_util::{handler, prometheus};
+use crate::controller::{chain, event, export, report, stream};
+use ::api_util::{handler, prometheus, trace};
 use ::axum::{Router, middleware::from_fn, routing::get};
 
 pub fn init_app() -> Router {
-    let router = Router::new()
+    Router::new()
+        .route("/events", get(event::find_events))
+        .route("/events/export", get(export::export_events))
+        .route("/events/stream", get(stream::stream_events))
+        .route("/events/verify", get(chain::verify_chain))
+        .route("/reports", get(report::find_reports))
+        .route("/reports/{id}", get(report::get_report))
         .route("/healthcheck", get(handler::healthcheck))
-        .route_layer(from_fn(prometheus::track_metrics));
-
-    router
+        .route_layer(from_fn(prometheus::track_metrics))
+        .layer(from_fn(trace::trace_context))
 }
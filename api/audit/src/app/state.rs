@@ -1,24 +1,122 @@
-use super::config::AppConfig;
-use ::api_util::{Error, amqp::AMQPPool, amqp_init, db_init, migrate::MigrateExt};
-use ::surrealdb::{Surreal, engine::remote::ws::Client};
-use ::tokio::sync::OnceCell;
+use super::config::{AppConfig, Feed};
+use crate::{
+    model::{ChainHead, Checkpoint},
+    repository::{AuditEventEntity, AuditEventRecord, ChainRepository, EventRepository},
+};
+use ::api_util::{Error, amqp::AMQPPool, amqp_init, db_init, env, migrate::MigrateExt};
+use ::chrono::Utc;
+use ::surrealdb::{
+    Surreal,
+    engine::remote::ws::{Client, Ws},
+    opt::auth::Database,
+};
+use ::std::sync::Arc;
+use ::tokio::sync::{Mutex, OnceCell, broadcast};
+use ::uuid::Uuid;
 
 static APP: OnceCell<AppState> = OnceCell::const_new();
+const LIVE_CHANNEL_CAPACITY: usize = 1024;
 
 pub struct AppState {
     pub cfg:  AppConfig,
+    /// Identifies this replica as the holder of leases
+    pub instance: String,
     pub amqp: AMQPPool,
     pub db: Surreal<Client>,
+    /// Database holding the audited tables, the audit database unless configured apart
+    pub feed_db: Surreal<Client>,
+    /// Last link of the audit hash chain as seen by this replica, held while a record is
+    /// appended
+    pub chain: Mutex<ChainHead>,
+    /// Newly stored records, waking live stream subscribers up
+    pub live: broadcast::Sender<Arc<AuditEventRecord>>,
+}
+
+impl AppState {
+    /// Links the record to the chain head and stores it, returns whether it was stored.
+    ///
+    /// Records are appended one at a time so each links to the hash of the previous one,
+    /// redelivered records are ignored.
+    ///
+    /// Replicas are serialised by the unique `seq` index. A replica whose head is behind
+    /// another's fails on the index, reloads the head from the database and the delivery
+    /// is retried, so the chain never forks.
+    pub async fn append_audit_event(&self, mut entity: AuditEventEntity) -> Result<bool, Error> {
+        let mut head = self.chain.lock().await;
+
+        entity.seq = head.seq + 1;
+        entity.prev_hash = head.hash.clone();
+        entity.hash = entity.chain_content().hash(&entity.prev_hash);
+
+        let (seq, hash) = (entity.seq, entity.hash.clone());
+        let record = entity.to_record(Utc::now().timestamp());
+        match self.db.create_audit_event(entity).await {
+            Ok(true) => {
+                *head = ChainHead { seq, hash };
+                // Nobody may be listening
+                let _ = self.live.send(Arc::new(record));
+                Ok(true)
+            }
+            Ok(false) => Ok(false),
+            Err(err) => {
+                // The write may have landed anyway, the database is the source of truth
+                if let Ok(Some(stored_head)) = self.db.find_chain_head().await {
+                    *head = stored_head;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Signs the current chain head unless it is already covered by a checkpoint
+    pub async fn create_checkpoint(&self) -> Result<Option<i64>, Error> {
+        let head = self.chain.lock().await.clone();
+        let last_seq = self.db.find_last_checkpoint_seq().await?.unwrap_or(0);
+
+        if head.seq <= last_seq {
+            return Ok(None);
+        }
+
+        self.db
+            .create_checkpoint(Checkpoint::sign(
+                head.seq,
+                head.hash,
+                self.cfg.chain.checkpoint_secret.as_bytes(),
+            ))
+            .await?;
+
+        Ok(Some(head.seq))
+    }
 }
 
 pub async fn init_state() -> Result<&'static AppState, Error> {
     let cfg = AppConfig::new();
+    if cfg.chain.checkpoint_secret.is_empty() || cfg.chain.checkpoint_secret == "secret" {
+        return Err(Error::Unknown(
+            "AUDIT_CHECKPOINT_SECRET must be set to a secret of its own",
+        ));
+    }
+
     let amqp = amqp_init!();
     let db = db_init!();
 
     db.migrate_up().await?;
-    
-    let state = AppState { cfg, amqp, db };
+
+    let feed_db = connect_feed_db(&cfg.feed).await?;
+
+    let chain = Mutex::new(db.find_chain_head().await?.unwrap_or_default());
+
+    let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+
+    let state = AppState {
+        cfg,
+        instance: Uuid::new_v4().to_string(),
+        amqp,
+        db,
+        feed_db,
+        chain,
+        live,
+    };
     
     APP.set(state)
         .map_err(|_| Error::Unknown("Application state already set"))?;
@@ -26,6 +124,25 @@ pub async fn init_state() -> Result<&'static AppState, Error> {
     Ok(APP.get().unwrap())
 }
 
+async fn connect_feed_db(feed: &Feed) -> Result<Surreal<Client>, Error> {
+    let feed_db = Surreal::<Client>::init();
+
+    feed_db
+        .connect::<Ws>(env::get_var_or_default("DB_URL", "surrealdb:8000"))
+        .await?;
+
+    feed_db
+        .signin(Database {
+            namespace: env::get_var_or_default("DB_NAMESPACE", "u2"),
+            database: feed.database,
+            username: feed.user,
+            password: feed.password,
+        })
+        .await?;
+
+    Ok(feed_db)
+}
+
 pub fn get_state() -> &'static AppState {
     APP.get().expect("Application state is not set")
 }
\ No newline at end of file
@@ -0,0 +1,37 @@
+use ::api_util::{
+    AuthError,
+    auth::{AUDIT_PERMISSION, Capabilities, Claims},
+};
+
+/// Authorisation model of every audit endpoint.
+///
+/// The audit VIEW capability is required throughout and grants every record. The chain
+/// and the reports are reserved to audit managers and administrators.
+pub struct AuditAccess {
+    full: bool,
+}
+
+impl AuditAccess {
+    pub fn from_claims(claims: &Claims) -> Result<Self, AuthError> {
+        claims.has_capabilities(AUDIT_PERMISSION, Capabilities::VIEW)?;
+
+        let capabilities = claims
+            .auth
+            .as_ref()
+            .map(|auth| auth.permissions.get_or_default(AUDIT_PERMISSION))
+            .unwrap_or(Capabilities::NONE);
+
+        Ok(Self {
+            full: capabilities.intersects(Capabilities::MANAGER | Capabilities::ADMINISTRATOR),
+        })
+    }
+
+    /// Required by the chain and the reports
+    pub fn require_full(&self) -> Result<(), AuthError> {
+        if self.full {
+            Ok(())
+        } else {
+            Err(AuthError::AccessForbidden)
+        }
+    }
+}
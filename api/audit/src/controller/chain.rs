@@ -0,0 +1,200 @@
+use super::access::AuditAccess;
+use crate::{
+    app::get_state,
+    model::{ArchiveManifest, ChainHead, Checkpoint, GENESIS_HASH},
+    repository::{ArchiveRepository, ChainLinkEntity, ChainRepository},
+};
+use ::api_util::{Error, auth::Claims};
+use ::axum::{Json, response::IntoResponse};
+use ::serde::Serialize;
+use ::std::collections::HashMap;
+
+const VERIFY_BATCH_SIZE: u32 = 1000;
+
+#[derive(Serialize)]
+pub struct BrokenLink {
+    pub seq: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub reason: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct VerifyChainBody {
+    pub valid: bool,
+    pub verified: i64,
+    pub checkpoints: usize,
+    pub archives: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken: Option<BrokenLink>,
+}
+
+/// Walks the audit hash chain from the first record and reports the first broken link.
+///
+/// Edited records fail their own hash, deleted ones leave a gap in `seq`, and rewriting
+/// the chain suffix is caught by the signed checkpoints it crosses. Archived records are
+/// checked against their archive files, which are chained through their manifests.
+/// Categories are left out of the hash, they follow from the action and must match it.
+pub async fn verify_chain(claims: Claims<'_>) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    AuditAccess::from_claims(&claims)?.require_full()?;
+
+    let secret = state.cfg.chain.checkpoint_secret.as_bytes();
+    let checkpoints = state.db.find_checkpoints().await?;
+
+    if let Some(checkpoint) = checkpoints
+        .iter()
+        .find(|checkpoint| !checkpoint.verify(secret))
+    {
+        return Ok(Json(broken(
+            0,
+            (checkpoints.len(), 0),
+            checkpoint.seq,
+            None,
+            "invalid checkpoint signature",
+        )));
+    }
+
+    let archives = state.db.find_archives().await?;
+
+    let mut prev_archive_hash = GENESIS_HASH;
+    for manifest in &archives {
+        let reason = if manifest.prev_hash != prev_archive_hash {
+            Some("previous archive hash mismatch")
+        } else if manifest.compute_hash() != manifest.hash {
+            Some("archive manifest hash mismatch")
+        } else {
+            state.verify_archive(manifest).await?
+        };
+
+        if let Some(reason) = reason {
+            return Ok(Json(broken(
+                0,
+                (checkpoints.len(), archives.len()),
+                manifest.first_seq,
+                Some(manifest.name.clone()),
+                reason,
+            )));
+        }
+
+        prev_archive_hash = manifest.hash.as_str();
+    }
+
+    let archive_links = archives
+        .iter()
+        .map(|manifest| (manifest.name.as_str(), manifest))
+        .collect::<HashMap<_, _>>();
+
+    let checkpoint_hashes = checkpoints
+        .iter()
+        .map(|Checkpoint { seq, hash, .. }| (*seq, hash.as_str()))
+        .collect::<HashMap<_, _>>();
+
+    let mut prev = ChainHead::default();
+    loop {
+        let links = state
+            .db
+            .find_chain_links(prev.seq, VERIFY_BATCH_SIZE)
+            .await?;
+        let batch_len = links.len();
+
+        for link in links {
+            let reason = if link.seq != prev.seq + 1 {
+                Some("missing record")
+            } else if link.prev_hash != prev.hash {
+                Some("previous hash mismatch")
+            } else if link.category != link.action.category() {
+                Some("category mismatch")
+            } else if let Some(reason) = content_error(&link, &archive_links) {
+                Some(reason)
+            } else if checkpoint_hashes
+                .get(&link.seq)
+                .is_some_and(|hash| *hash != link.hash)
+            {
+                Some("checkpoint hash mismatch")
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                return Ok(Json(broken(
+                    prev.seq,
+                    (checkpoints.len(), archives.len()),
+                    prev.seq + 1,
+                    Some(link.id),
+                    reason,
+                )));
+            }
+
+            prev = ChainHead {
+                seq: link.seq,
+                hash: link.hash,
+            };
+        }
+
+        if batch_len < VERIFY_BATCH_SIZE as usize {
+            break;
+        }
+    }
+
+    // Records removed from the end of the chain are only noticed against what is known
+    // to have been written
+    let last_checkpoint_seq = checkpoints.last().map_or(0, |checkpoint| checkpoint.seq);
+    let head_seq = state.chain.lock().await.seq;
+    if last_checkpoint_seq.max(head_seq) > prev.seq {
+        return Ok(Json(broken(
+            prev.seq,
+            (checkpoints.len(), archives.len()),
+            prev.seq + 1,
+            None,
+            "missing record",
+        )));
+    }
+
+    Ok(Json(VerifyChainBody {
+        valid: true,
+        verified: prev.seq,
+        checkpoints: checkpoints.len(),
+        archives: archives.len(),
+        broken: None,
+    }))
+}
+
+/// Archived records keep only their chain link, their content is verified with the
+/// archive file
+fn content_error(
+    link: &ChainLinkEntity,
+    archives: &HashMap<&str, &ArchiveManifest>,
+) -> Option<&'static str> {
+    let Some(archive) = &link.archive else {
+        return (link.chain_content().hash(&link.prev_hash) != link.hash)
+            .then_some("content hash mismatch");
+    };
+
+    match archives.get(archive.as_str()) {
+        None => Some("unknown archive"),
+        Some(manifest) if link.seq < manifest.first_seq || link.seq > manifest.last_seq => {
+            Some("record outside of its archive")
+        }
+        Some(manifest) if link.seq == manifest.last_seq && link.hash != manifest.last_hash => {
+            Some("archive hash mismatch")
+        }
+        _ => None,
+    }
+}
+
+fn broken(
+    verified: i64,
+    (checkpoints, archives): (usize, usize),
+    seq: i64,
+    id: Option<String>,
+    reason: &'static str,
+) -> VerifyChainBody {
+    VerifyChainBody {
+        valid: false,
+        verified,
+        checkpoints,
+        archives,
+        broken: Some(BrokenLink { seq, id, reason }),
+    }
+}
@@ -0,0 +1,101 @@
+use super::access::AuditAccess;
+use crate::{
+    app::get_state,
+    model::{AuditAction, AuditCategory, AuditCursor, AuditEventFilter, SortOrder},
+    repository::{AuditEventRecord, EventRepository},
+};
+use ::api_util::{Error, auth::Claims};
+use ::axum::{Json, extract::Query, response::IntoResponse};
+use ::serde::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_LIMIT: u32 = 50;
+const MAX_PAGE_LIMIT: u32 = 500;
+
+#[derive(Deserialize)]
+pub struct AuditEventsPayload {
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub entity_type: Option<String>,
+    #[serde(default)]
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub category: Option<AuditCategory>,
+    #[serde(default)]
+    pub action: Option<AuditAction>,
+    #[serde(default)]
+    pub changed_field: Option<String>,
+    #[serde(default)]
+    pub from: Option<i64>,
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Serialize)]
+pub struct AuditEventsBody {
+    pub items: Vec<AuditEventRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Searches audit records, `from` is inclusive and `to` exclusive, both in unix seconds.
+/// `changed_field` matches the dotted field names changed by updates, e.g. `profile.rank`
+pub async fn find_events(
+    claims: Claims<'_>,
+    Query(payload): Query<AuditEventsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    AuditAccess::from_claims(&claims)?;
+
+    let cursor = match payload.cursor {
+        Some(cursor) => {
+            Some(AuditCursor::decode(cursor).ok_or(Error::BadRequest("Invalid cursor".into()))?)
+        }
+        None => None,
+    };
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    let filter = AuditEventFilter {
+        actor: payload.actor,
+        service: payload.service,
+        entity_type: payload.entity_type,
+        entity_id: payload.entity_id,
+        category: payload.category,
+        action: payload.action,
+        changed_field: payload.changed_field,
+        from: payload.from,
+        to: payload.to,
+    };
+
+    // One extra record tells whether another page follows
+    let mut items = state
+        .db
+        .find_audit_events(filter, cursor, payload.order, limit + 1)
+        .await?;
+
+    let next_cursor = if items.len() > limit as usize {
+        items.truncate(limit as usize);
+        items.last().map(|event| {
+            AuditCursor {
+                occurred_at: event.occurred_at,
+                id: event.id.clone(),
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(AuditEventsBody { items, next_cursor }))
+}
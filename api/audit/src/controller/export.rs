@@ -0,0 +1,179 @@
+use super::access::AuditAccess;
+use crate::{
+    app::get_state,
+    model::{AuditAction, AuditCategory, AuditCursor, AuditEventFilter, SortOrder},
+    repository::{AuditEventRecord, EventRepository},
+};
+use ::api_util::{Error, auth::Claims};
+use ::axum::{
+    body::Body,
+    extract::Query,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{AppendHeaders, IntoResponse},
+};
+use ::chrono::Utc;
+use ::futures::{StreamExt, stream};
+use ::serde::Deserialize;
+
+const EXPORT_BATCH_SIZE: u32 = 1000;
+const CSV_HEADER: &str = "id,occurred_at,received_at,service,actor,entity_type,entity_id,\
+    category,action,changed_fields,correlation_id,payload\n";
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Jsonl,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+
+    fn line(&self, event: &AuditEventRecord) -> Result<String, serde_json::Error> {
+        match self {
+            Self::Csv => {
+                let payload = match &event.payload {
+                    Some(payload) => serde_json::to_string(payload)?,
+                    None => String::new(),
+                };
+
+                let fields = [
+                    event.id.as_str(),
+                    &event.occurred_at.to_string(),
+                    &event.received_at.to_string(),
+                    &event.service,
+                    event.actor.as_deref().unwrap_or_default(),
+                    &event.entity_type,
+                    &event.entity_id,
+                    event.category.as_str(),
+                    event.action.as_str(),
+                    &event.changed_fields.join(";"),
+                    event.correlation_id.as_deref().unwrap_or_default(),
+                    &payload,
+                ]
+                .map(csv_field);
+
+                Ok(format!("{}\n", fields.join(",")))
+            }
+            Self::Jsonl => Ok(format!("{}\n", serde_json::to_string(event)?)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportEventsPayload {
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub entity_type: Option<String>,
+    #[serde(default)]
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub category: Option<AuditCategory>,
+    #[serde(default)]
+    pub action: Option<AuditAction>,
+    #[serde(default)]
+    pub changed_field: Option<String>,
+    #[serde(default)]
+    pub from: Option<i64>,
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Streams every audit record matching the filter in chronological order, reading the
+/// database page by page so that large exports are never held in memory
+pub async fn export_events(
+    claims: Claims<'_>,
+    Query(payload): Query<ExportEventsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    AuditAccess::from_claims(&claims)?;
+
+    let format = payload.format;
+    let filter = AuditEventFilter {
+        actor: payload.actor,
+        service: payload.service,
+        entity_type: payload.entity_type,
+        entity_id: payload.entity_id,
+        category: payload.category,
+        action: payload.action,
+        changed_field: payload.changed_field,
+        from: payload.from,
+        to: payload.to,
+    };
+
+    let pages = stream::try_unfold((None, false), move |(cursor, done)| {
+        let filter = filter.clone();
+        async move {
+            if done {
+                return Ok(None);
+            }
+
+            let events = get_state()
+                .db
+                .find_audit_events(filter, cursor, SortOrder::Asc, EXPORT_BATCH_SIZE)
+                .await?;
+
+            let done = events.len() < EXPORT_BATCH_SIZE as usize;
+            let cursor = events.last().map(|event| AuditCursor {
+                occurred_at: event.occurred_at,
+                id: event.id.clone(),
+            });
+            let chunk = events
+                .iter()
+                .map(|event| format.line(event))
+                .collect::<Result<String, _>>()
+                .map_err(Error::from)?;
+
+            // Boxed, the stream error is moved around with every chunk
+            Ok::<_, Box<Error>>(Some((chunk, (cursor, done))))
+        }
+    });
+
+    let header = match format {
+        ExportFormat::Csv => Some(Ok(CSV_HEADER.to_string())),
+        ExportFormat::Jsonl => None,
+    };
+
+    let headers = AppendHeaders([
+        (CONTENT_TYPE, format.content_type().to_string()),
+        (
+            CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"audit-{}.{}\"",
+                Utc::now().timestamp(),
+                format.extension()
+            ),
+        ),
+    ]);
+
+    Ok((
+        headers,
+        Body::from_stream(stream::iter(header).chain(pages)),
+    ))
+}
+
+/// Quotes fields holding separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
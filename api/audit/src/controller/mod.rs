@@ -0,0 +1,6 @@
+mod access;
+pub mod chain;
+pub mod event;
+pub mod export;
+pub mod report;
+pub mod stream;
\ No newline at end of file
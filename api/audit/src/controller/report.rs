@@ -0,0 +1,80 @@
+use super::access::AuditAccess;
+use crate::{
+    app::get_state,
+    model::{ReportPeriod, ReportSummary},
+    repository::ReportRepository,
+};
+use ::api_util::{Error, auth::Claims};
+use ::axum::{
+    Json,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use ::serde::{Deserialize, Serialize};
+
+const DEFAULT_REPORTS_LIMIT: u32 = 30;
+const MAX_REPORTS_LIMIT: u32 = 365;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Html,
+}
+
+#[derive(Deserialize)]
+pub struct ReportsPayload {
+    #[serde(default)]
+    pub period: Option<ReportPeriod>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ReportsBody {
+    pub items: Vec<ReportSummary>,
+}
+
+#[derive(Deserialize)]
+pub struct ReportPayload {
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Lists the generated reports, most recent period first
+pub async fn find_reports(
+    claims: Claims<'_>,
+    Query(payload): Query<ReportsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    AuditAccess::from_claims(&claims)?.require_full()?;
+
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_REPORTS_LIMIT)
+        .clamp(1, MAX_REPORTS_LIMIT);
+    let items = state.db.find_reports(payload.period, limit).await?;
+
+    Ok(Json(ReportsBody { items }))
+}
+
+/// Returns one report as JSON or as a printable HTML page
+pub async fn get_report(
+    claims: Claims<'_>,
+    Path(id): Path<String>,
+    Query(payload): Query<ReportPayload>,
+) -> Result<Response, Error> {
+    let state = get_state();
+    AuditAccess::from_claims(&claims)?.require_full()?;
+
+    let Some(report) = state.db.find_report(id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    Ok(match payload.format {
+        ReportFormat::Json => Json(report).into_response(),
+        ReportFormat::Html => Html(report.to_html()).into_response(),
+    })
+}
@@ -0,0 +1,183 @@
+use super::access::AuditAccess;
+use crate::{
+    app::get_state,
+    model::{AuditAction, AuditCategory, AuditEventFilter},
+    repository::{AuditEventRecord, EventRepository},
+};
+use ::api_util::{Error, auth::Claims};
+use ::axum::{
+    extract::Query,
+    http::HeaderMap,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use ::chrono::Utc;
+use ::futures::stream;
+use ::serde::Deserialize;
+use ::std::{collections::VecDeque, future, sync::Arc};
+use ::tokio::{
+    sync::broadcast::{Receiver, error::RecvError},
+    time::{Duration, timeout},
+};
+
+const BATCH_SIZE: u32 = 500;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const LAST_EVENT_ID: &str = "last-event-id";
+
+#[derive(Deserialize)]
+pub struct StreamEventsPayload {
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub entity_type: Option<String>,
+    #[serde(default)]
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub category: Option<AuditCategory>,
+    #[serde(default)]
+    pub action: Option<AuditAction>,
+    #[serde(default)]
+    pub changed_field: Option<String>,
+}
+
+struct StreamState {
+    /// Expiration of the token the stream was opened with
+    expires_at: i64,
+    filter: AuditEventFilter,
+    /// Records stored by this replica, they only wake the stream up
+    live: Receiver<Arc<AuditEventRecord>>,
+    pending: VecDeque<AuditEventRecord>,
+    last_seq: i64,
+}
+
+impl StreamState {
+    /// Next record to push, read from the database after the last one pushed so that
+    /// records stored by any replica are pushed in chain order. `None` ends the stream.
+    async fn next(&mut self) -> Option<Result<AuditEventRecord, Error>> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                self.last_seq = record.seq;
+                return Some(Ok(record));
+            }
+
+            match get_state()
+                .db
+                .find_audit_events_after(self.filter.clone(), self.last_seq, BATCH_SIZE)
+                .await
+            {
+                Ok(records) if records.is_empty() => self.wait().await,
+                Ok(records) => self.pending.extend(records),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Waits for this replica to store a matching record, records stored by other replicas
+    /// are picked up by the next poll
+    async fn wait(&mut self) {
+        let Self {
+            filter,
+            live,
+            last_seq,
+            ..
+        } = self;
+
+        let woken = async {
+            loop {
+                match live.recv().await {
+                    Ok(record) if record.seq > *last_seq && matches(filter, &record) => break,
+                    Ok(_) => (),
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => future::pending().await,
+                }
+            }
+        };
+        let _ = timeout(POLL_INTERVAL, woken).await;
+    }
+}
+
+/// Pushes newly stored audit records matching the filter as Server-Sent Events.
+///
+/// Records are read from the database, polled every few seconds and as soon as this
+/// replica stores a matching one. Events carry the chain `seq` as their id, a reconnect
+/// with `Last-Event-ID` first replays what was missed. The stream ends when the token it was opened with expires.
+pub async fn stream_events(
+    claims: Claims<'_>,
+    headers: HeaderMap,
+    Query(payload): Query<StreamEventsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    let state = get_state();
+    AuditAccess::from_claims(&claims)?;
+
+    let filter = AuditEventFilter {
+        actor: payload.actor,
+        service: payload.service,
+        entity_type: payload.entity_type,
+        entity_id: payload.entity_id,
+        category: payload.category,
+        action: payload.action,
+        changed_field: payload.changed_field,
+        ..Default::default()
+    };
+
+    let live = state.live.subscribe();
+    let last_event_id = headers
+        .get(LAST_EVENT_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok());
+    let last_seq = match last_event_id {
+        Some(seq) => seq,
+        None => state.chain.lock().await.seq,
+    };
+
+    let stream_state = StreamState {
+        expires_at: claims.exp as i64,
+        filter,
+        live,
+        pending: VecDeque::new(),
+        last_seq,
+    };
+
+    let events = stream::unfold(stream_state, |mut stream_state| async move {
+        let expires_in = (stream_state.expires_at - Utc::now().timestamp()).max(0);
+        let next = timeout(Duration::from_secs(expires_in as u64), stream_state.next());
+
+        let record = match next.await.ok()?? {
+            Ok(record) => record,
+            Err(err) => return Some((Err(err), stream_state)),
+        };
+
+        let event = Event::default()
+            .id(record.seq.to_string())
+            .event("audit")
+            .json_data(&record)
+            .map_err(|_| Error::Unknown("Audit event serialization failed"));
+
+        Some((event, stream_state))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn matches(filter: &AuditEventFilter, record: &AuditEventRecord) -> bool {
+    fn accepts(expected: &Option<String>, value: &str) -> bool {
+        expected.as_deref().is_none_or(|expected| expected == value)
+    }
+
+    accepts(&filter.actor, record.actor.as_deref().unwrap_or_default())
+        && accepts(&filter.service, &record.service)
+        && accepts(&filter.entity_type, &record.entity_type)
+        && accepts(&filter.entity_id, &record.entity_id)
+        && filter
+            .category
+            .is_none_or(|category| category == record.category)
+        && filter.action.is_none_or(|action| action == record.action)
+        && filter
+            .changed_field
+            .as_ref()
+            .is_none_or(|field| record.changed_fields.contains(field))
+}
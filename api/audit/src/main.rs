@@ -1,11 +1,15 @@
 mod amqp;
 mod app;
+mod controller;
+mod model;
+mod repository;
 
 use crate::{
     amqp::init_amqp,
-    app::{init_app, init_state},
+    app::{get_state, init_app, init_state},
 };
 use ::api_util::{Error, console::*, log, panic::*, server, shutdown::*};
+use ::tokio::time::{Duration, sleep};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<Error>> {
@@ -21,8 +25,60 @@ async fn main() -> Result<(), Box<Error>> {
 
     init_amqp().await?;
 
+    tokio::spawn(async {
+        let state = get_state();
+        let timeout = Duration::from_secs(state.cfg.chain.checkpoint_interval);
+        loop {
+            sleep(timeout).await;
+            match state.create_checkpoint().await {
+                Ok(Some(seq)) => log::info!("audit chain checkpoint created at {seq}"),
+                Ok(None) => (),
+                Err(err) => log::error!("creating audit chain checkpoint: {err}"),
+            }
+        }
+    });
+
+    tokio::spawn(async {
+        let state = get_state();
+        let timeout = Duration::from_secs(state.cfg.retention.archive_interval);
+        loop {
+            sleep(timeout).await;
+            match state.archive_expired().await {
+                Ok(0) => (),
+                Ok(archived) => log::info!("{archived} audit records archived"),
+                Err(err) => log::error!("archiving audit records: {err}"),
+            }
+        }
+    });
+
+    tokio::spawn(async {
+        let state = get_state();
+        let timeout = Duration::from_secs(state.cfg.feed.interval);
+        while !state.cfg.feed.tables.is_empty() {
+            sleep(timeout).await;
+            match state.tail_change_feeds().await {
+                Ok(0) => (),
+                Ok(stored) => log::info!("{stored} audit records captured from change feeds"),
+                Err(err) => log::error!("tailing change feeds: {err}"),
+            }
+        }
+    });
+
+    tokio::spawn(async {
+        let state = get_state();
+        let timeout = Duration::from_secs(state.cfg.report.interval);
+        loop {
+            match state.generate_reports().await {
+                Ok(0) => (),
+                Ok(generated) => log::info!("{generated} audit reports generated"),
+                Err(err) => log::error!("generating audit reports: {err}"),
+            }
+            sleep(timeout).await;
+        }
+    });
+
     server::start_server(init_app(), shutdown_handle).await;
 
-    print_service_stopped();
+    print_service_stopped().await;
     Ok(())
 }
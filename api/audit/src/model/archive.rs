@@ -0,0 +1,49 @@
+use super::canonical_json;
+use ::serde::{Deserialize, Serialize};
+use ::sha2::{Digest, Sha256};
+
+/// Expired records of one retention rule, `entity_type` selects a single type while
+/// `excluded` keeps the types with their own rule out of the default one
+#[derive(Serialize, Clone)]
+pub struct ExpiredFilter {
+    pub entity_type: Option<&'static str>,
+    pub excluded: Vec<&'static str>,
+    pub before: i64,
+}
+
+/// Describes an archive file and links it to the previous archive.
+///
+/// `first_seq`, `last_seq` and `last_hash` tie the archive to the audit hash chain, while
+/// `prev_hash` and `hash` chain the manifests themselves.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchiveManifest {
+    pub name: String,
+    pub seq: i64,
+    pub file: String,
+    pub checksum: String,
+    pub records: usize,
+    pub first_seq: i64,
+    pub last_seq: i64,
+    pub last_hash: String,
+    pub created_at: i64,
+    pub prev_hash: String,
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl ArchiveManifest {
+    /// Hex encoded SHA-256 of the previous manifest hash followed by the canonical JSON
+    /// of the manifest without its own hash
+    pub fn compute_hash(&self) -> String {
+        let mut content = serde_json::to_value(self).unwrap_or_default();
+        if let Some(content) = content.as_object_mut() {
+            content.remove("hash");
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.prev_hash.as_bytes());
+        hasher.update(canonical_json(&content).as_bytes());
+
+        hex::encode(hasher.finalize())
+    }
+}
@@ -0,0 +1,57 @@
+use ::api_util::{auth_event::AuthAction, entity_event::EntityAction};
+use ::serde::{Deserialize, Serialize};
+
+/// Kind of activity an audit record describes, queried separately by consumers
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditCategory {
+    #[default]
+    Entity,
+    Auth,
+}
+
+impl AuditCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Entity => "entity",
+            Self::Auth => "auth",
+        }
+    }
+}
+
+/// Action of an audit record. Both kinds serialize as their plain name, so entity
+/// records stored before authentication events were audited keep their hash.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum AuditAction {
+    Entity(EntityAction),
+    Auth(AuthAction),
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Entity(action) => action.as_str(),
+            Self::Auth(action) => action.as_str(),
+        }
+    }
+
+    pub fn category(&self) -> AuditCategory {
+        match self {
+            Self::Entity(_) => AuditCategory::Entity,
+            Self::Auth(_) => AuditCategory::Auth,
+        }
+    }
+}
+
+impl From<EntityAction> for AuditAction {
+    fn from(action: EntityAction) -> Self {
+        Self::Entity(action)
+    }
+}
+
+impl From<AuthAction> for AuditAction {
+    fn from(action: AuthAction) -> Self {
+        Self::Auth(action)
+    }
+}
@@ -0,0 +1,62 @@
+use super::{AuditAction, AuditCategory};
+use ::base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ::serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    /// Direction of the `ORDER BY` clause
+    pub fn direction(&self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+
+    /// Comparison selecting the records which come after a cursor in this order
+    pub fn after(&self) -> &'static str {
+        match self {
+            Self::Asc => ">",
+            Self::Desc => "<",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct AuditEventFilter {
+    pub actor: Option<String>,
+    pub service: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub category: Option<AuditCategory>,
+    pub action: Option<AuditAction>,
+    pub changed_field: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+/// Position of the last returned record, records are ordered by `occurred_at` then `id`
+#[derive(Deserialize, Serialize)]
+pub struct AuditCursor {
+    pub occurred_at: i64,
+    pub id: String,
+}
+
+impl AuditCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: impl AsRef<str>) -> Option<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor.as_ref())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    }
+}
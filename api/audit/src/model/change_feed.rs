@@ -0,0 +1,158 @@
+use ::api_util::entity_event::EntityAction;
+use ::serde::Deserialize;
+use ::serde_json::{Map, Value};
+
+/// Changes of one table committed under a single versionstamp
+#[derive(Deserialize)]
+pub struct ChangeSet {
+    pub versionstamp: i64,
+    #[serde(default)]
+    pub changes: Vec<Map<String, Value>>,
+}
+
+impl ChangeSet {
+    /// `SINCE` of the change sets after this one. Feeds report the whole 10-byte
+    /// versionstamp while `SHOW CHANGES` reads the 8 leading bytes alone.
+    pub fn next_since(&self) -> i64 {
+        (self.versionstamp >> 16) + 1
+    }
+}
+
+/// Record change read from a table change feed
+pub struct FeedChange {
+    pub action: EntityAction,
+    pub entity_id: String,
+    pub actor: Option<String>,
+    pub occurred_at: Option<i64>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl FeedChange {
+    /// Table definitions and other non-record changes are skipped.
+    ///
+    /// Tables are fed with `INCLUDE ORIGINAL`, so an update carries the current state
+    /// with the patch leading back to the previous one while a creation carries the new
+    /// state alone. A deletion only carries the record id. When the patch can't be
+    /// applied the update keeps the current state alone.
+    pub fn parse(table: &str, mut change: Map<String, Value>) -> Option<Self> {
+        let (action, before, after) = if let Some(current) = change.remove("current") {
+            let before = change
+                .remove("update")
+                .and_then(|patch| revert(current.clone(), patch));
+            (EntityAction::Updated, before, Some(current))
+        } else if let Some(state) = change.remove("update").or_else(|| change.remove("create")) {
+            (EntityAction::Created, None, Some(state))
+        } else {
+            (EntityAction::Deleted, Some(change.remove("delete")?), None)
+        };
+
+        let state = after.as_ref().or(before.as_ref())?;
+        let entity_id = record_key(table, state.get("id")?.as_str()?);
+        let metadata = after.as_ref().and_then(|after| after.get("metadata"));
+
+        Some(Self {
+            action,
+            entity_id,
+            actor: metadata
+                .and_then(|metadata| metadata.get("updated_by"))
+                .and_then(Value::as_str)
+                .map(|actor| record_key("users", actor)),
+            occurred_at: metadata
+                .and_then(|metadata| metadata.get("updated_at"))
+                .and_then(Value::as_i64),
+            before,
+            after,
+        })
+    }
+}
+
+/// Previous state of a record, rebuilt by applying the patch operations of a change
+/// to its current state in order
+fn revert(mut state: Value, patch: Value) -> Option<Value> {
+    let Value::Array(operations) = patch else {
+        return None;
+    };
+
+    for operation in operations {
+        let keys = operation
+            .get("path")?
+            .as_str()?
+            .split('/')
+            .skip(1)
+            .map(|key| key.trim_matches(['`', '⟨', '⟩']))
+            .collect::<Vec<_>>();
+        let (key, parents) = keys.split_last()?;
+        let parent = parents
+            .iter()
+            .try_fold(&mut state, |value, key| match value {
+                Value::Object(fields) => fields.get_mut(*key),
+                Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?),
+                _ => None,
+            })?;
+
+        apply(
+            parent,
+            key,
+            operation.get("op")?.as_str()?,
+            operation.get("value"),
+        )?;
+    }
+
+    Some(state)
+}
+
+/// Applies one patch operation to the `key` child of `parent`. Patches only remove
+/// trailing array items, so a removal drops every item from that index on.
+fn apply(parent: &mut Value, key: &str, op: &str, value: Option<&Value>) -> Option<()> {
+    match (op, parent) {
+        ("add" | "replace", Value::Object(fields)) => {
+            fields.insert(key.to_string(), value?.clone());
+        }
+        ("add" | "replace", Value::Array(items)) => {
+            let index = key.parse::<usize>().ok()?.min(items.len());
+            if op == "replace" && index < items.len() {
+                items[index] = value?.clone();
+            } else {
+                items.insert(index, value?.clone());
+            }
+        }
+        ("remove", Value::Object(fields)) => {
+            fields.remove(key);
+        }
+        ("remove", Value::Array(items)) => items.truncate(key.parse().ok()?),
+        ("change", parent) => {
+            let text = match parent {
+                Value::Object(fields) => fields.get_mut(key)?,
+                Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+            *text = Value::String(patch_text(text.as_str()?, value?.as_str()?)?);
+        }
+        _ => return None,
+    }
+
+    Some(())
+}
+
+/// Applies a diff-match-patch text, the form string changes are recorded in
+fn patch_text(text: &str, patch: &str) -> Option<String> {
+    let dmp = ::dmp::new();
+    let patches = dmp.patch_from_text(patch.to_string()).ok()?;
+    let (text, _) = dmp.patch_apply(&patches, text).ok()?;
+
+    Some(text.into_iter().collect())
+}
+
+/// Key of a record id rendered as `table:key`, escaped keys lose their brackets
+fn record_key(table: &str, id: &str) -> String {
+    let key = id
+        .strip_prefix(table)
+        .and_then(|key| key.strip_prefix(':'))
+        .unwrap_or(id);
+
+    key.strip_prefix('⟨')
+        .and_then(|key| key.strip_suffix('⟩'))
+        .unwrap_or(key)
+        .to_string()
+}
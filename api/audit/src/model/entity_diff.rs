@@ -0,0 +1,305 @@
+use ::api_util::entity_event::EntityAction;
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::{Map, Value};
+
+/// Stored in place of the values of sensitive fields
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// JSON Patch operation extended with the previous value of the field
+#[derive(Serialize, Deserialize)]
+pub struct FieldChange {
+    pub op: ChangeOp,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl FieldChange {
+    /// Dotted field name of the change, `/profile/rank` becomes `profile.rank`
+    pub fn field(&self) -> String {
+        self.path
+            .trim_start_matches('/')
+            .split('/')
+            .map(unescape_pointer)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// Updates carrying both states as objects are stored as a field-level diff together
+/// with the changed field names, other events keep their state snapshots. An entity
+/// whose state is a scalar or an array has no fields to name. Sensitive values are
+/// redacted either way.
+pub fn entity_payload(
+    action: EntityAction,
+    before: Option<Value>,
+    after: Option<Value>,
+    sensitive_fields: &[&str],
+) -> (Option<Map<String, Value>>, Vec<String>) {
+    let mut payload = Map::new();
+
+    if let (EntityAction::Updated, Some(Value::Object(before)), Some(Value::Object(after))) =
+        (action, &before, &after)
+    {
+        let mut changes = diff(before, after);
+        let changed_fields = changes.iter().map(|change| change.field()).collect();
+        changes
+            .iter_mut()
+            .for_each(|change| redact_change(change, sensitive_fields));
+
+        payload.insert(
+            "changes".to_string(),
+            serde_json::to_value(changes).unwrap_or_default(),
+        );
+
+        return (Some(payload), changed_fields);
+    }
+
+    for (key, mut state) in [("before", before), ("after", after)]
+        .into_iter()
+        .filter_map(|(key, state)| Some((key, state?)))
+    {
+        redact(&mut state, sensitive_fields);
+        payload.insert(key.to_string(), state);
+    }
+
+    ((!payload.is_empty()).then_some(payload), Vec::new())
+}
+
+/// Field-level diff of two entity states, nested objects are compared key by key while
+/// arrays and scalars are replaced as a whole
+pub fn diff(before: &Map<String, Value>, after: &Map<String, Value>) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_maps(&mut changes, "", before, after);
+
+    changes
+}
+
+fn diff_maps(
+    changes: &mut Vec<FieldChange>,
+    path: &str,
+    before: &Map<String, Value>,
+    after: &Map<String, Value>,
+) {
+    for (key, old) in before {
+        let path = format!("{path}/{}", escape_pointer(key));
+        match after.get(key) {
+            Some(value) => diff_into(changes, path, old, value),
+            None => changes.push(FieldChange {
+                op: ChangeOp::Remove,
+                path,
+                old: Some(old.clone()),
+                value: None,
+            }),
+        }
+    }
+    for (key, value) in after {
+        if !before.contains_key(key) {
+            changes.push(FieldChange {
+                op: ChangeOp::Add,
+                path: format!("{path}/{}", escape_pointer(key)),
+                old: None,
+                value: Some(value.clone()),
+            });
+        }
+    }
+}
+
+fn diff_into(changes: &mut Vec<FieldChange>, path: String, before: &Value, after: &Value) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => diff_maps(changes, &path, before, after),
+        (before, after) if before != after => changes.push(FieldChange {
+            op: ChangeOp::Replace,
+            path,
+            old: Some(before.clone()),
+            value: Some(after.clone()),
+        }),
+        _ => (),
+    }
+}
+
+/// Hides the values of sensitive fields of a change, keeping the fact that they changed
+pub fn redact_change(change: &mut FieldChange, sensitive_fields: &[&str]) {
+    let is_sensitive = change
+        .path
+        .split('/')
+        .any(|segment| is_sensitive(&unescape_pointer(segment), sensitive_fields));
+
+    for value in [&mut change.old, &mut change.value].into_iter().flatten() {
+        if is_sensitive {
+            *value = Value::from(REDACTED);
+        } else {
+            redact(value, sensitive_fields);
+        }
+    }
+}
+
+/// Hides the values of sensitive fields at every level of an entity state
+pub fn redact(value: &mut Value, sensitive_fields: &[&str]) {
+    match value {
+        Value::Object(map) => redact_map(map, sensitive_fields),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact(item, sensitive_fields)),
+        _ => (),
+    }
+}
+
+fn redact_map(map: &mut Map<String, Value>, sensitive_fields: &[&str]) {
+    for (key, value) in map.iter_mut() {
+        if is_sensitive(key, sensitive_fields) {
+            *value = Value::from(REDACTED);
+        } else {
+            redact(value, sensitive_fields);
+        }
+    }
+}
+
+fn is_sensitive(field: &str, sensitive_fields: &[&str]) -> bool {
+    sensitive_fields
+        .iter()
+        .any(|sensitive| sensitive.eq_ignore_ascii_case(field))
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_pointer(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{REDACTED, diff, entity_payload, redact_change};
+    use ::api_util::entity_event::EntityAction;
+    use ::serde_json::{Map, Value, json};
+
+    const SENSITIVE_FIELDS: &[&str] = &["password", "token"];
+
+    fn object(value: Value) -> Map<String, Value> {
+        let Value::Object(map) = value else {
+            panic!("not an object");
+        };
+
+        map
+    }
+
+    fn changes(before: Value, after: Value) -> Value {
+        serde_json::to_value(diff(&object(before), &object(after))).unwrap()
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let state = json!({"name": "alice", "profile": {"rank": 3}, "tags": ["a"]});
+
+        assert!(diff(&object(state.clone()), &object(state)).is_empty());
+    }
+
+    #[test]
+    fn diffs_added_removed_and_replaced_fields() {
+        assert_eq!(
+            changes(
+                json!({"email": "a@u2", "name": "alice"}),
+                json!({"name": "alicia", "phone": "123"}),
+            ),
+            json!([
+                {"op": "remove", "path": "/email", "old": "a@u2"},
+                {"op": "replace", "path": "/name", "old": "alice", "value": "alicia"},
+                {"op": "add", "path": "/phone", "value": "123"},
+            ])
+        );
+    }
+
+    #[test]
+    fn diffs_nested_fields_key_by_key() {
+        let before = json!({"profile": {"rank": 3, "unit": "a/b"}, "tags": ["a"]});
+        let after = json!({"profile": {"rank": 4, "unit": "a/b"}, "tags": ["a", "b"]});
+
+        let changes = diff(&object(before), &object(after));
+
+        assert_eq!(
+            serde_json::to_value(&changes).unwrap(),
+            json!([
+                {"op": "replace", "path": "/profile/rank", "old": 3, "value": 4},
+                {"op": "replace", "path": "/tags", "old": ["a"], "value": ["a", "b"]},
+            ])
+        );
+        assert_eq!(changes[0].field(), "profile.rank");
+    }
+
+    #[test]
+    fn escapes_pointer_segments() {
+        let changes = diff(&object(json!({})), &object(json!({"a/b~c": 1})));
+
+        assert_eq!(changes[0].path, "/a~1b~0c");
+        assert_eq!(changes[0].field(), "a/b~c");
+    }
+
+    #[test]
+    fn redacts_sensitive_fields_of_changes() {
+        let mut changes = diff(
+            &object(json!({"auth": {"password": "old"}, "profile": {"name": "a"}})),
+            &object(json!({
+                "auth": {"password": "new"},
+                "profile": {"name": "a", "session": {"token": "t"}},
+            })),
+        );
+        changes
+            .iter_mut()
+            .for_each(|change| redact_change(change, SENSITIVE_FIELDS));
+
+        assert_eq!(
+            serde_json::to_value(changes).unwrap(),
+            json!([
+                {"op": "replace", "path": "/auth/password", "old": REDACTED, "value": REDACTED},
+                {"op": "add", "path": "/profile/session", "value": {"token": REDACTED}},
+            ])
+        );
+    }
+
+    #[test]
+    fn stores_updates_as_redacted_diffs() {
+        let (payload, changed_fields) = entity_payload(
+            EntityAction::Updated,
+            Some(json!({"name": "alice", "password": "old"})),
+            Some(json!({"name": "alicia", "password": "new"})),
+            SENSITIVE_FIELDS,
+        );
+
+        assert_eq!(changed_fields, ["name", "password"]);
+        assert_eq!(
+            Value::Object(payload.unwrap()),
+            json!({"changes": [
+                {"op": "replace", "path": "/name", "old": "alice", "value": "alicia"},
+                {"op": "replace", "path": "/password", "old": REDACTED, "value": REDACTED},
+            ]})
+        );
+    }
+
+    #[test]
+    fn keeps_redacted_snapshots_of_other_events() {
+        let (payload, changed_fields) = entity_payload(
+            EntityAction::Created,
+            None,
+            Some(json!({"name": "alice", "password": "secret"})),
+            SENSITIVE_FIELDS,
+        );
+
+        assert!(changed_fields.is_empty());
+        assert_eq!(
+            Value::Object(payload.unwrap()),
+            json!({"after": {"name": "alice", "password": REDACTED}})
+        );
+    }
+}
@@ -0,0 +1,17 @@
+mod archive;
+mod audit_action;
+mod audit_query;
+mod chain;
+mod change_feed;
+mod entity_diff;
+mod report;
+
+pub use self::{
+    archive::*,
+    audit_action::*,
+    audit_query::*,
+    chain::*,
+    change_feed::*,
+    entity_diff::*,
+    report::*,
+};
\ No newline at end of file
@@ -0,0 +1,264 @@
+use super::AuditAction;
+use ::chrono::DateTime;
+use ::serde::{Deserialize, Serialize};
+use ::std::fmt::Write;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// 1970-01-01 was a Thursday, three days after the start of its ISO week
+const EPOCH_WEEKDAY: i64 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Daily,
+    Weekly,
+}
+
+impl ReportPeriod {
+    pub const ALL: [Self; 2] = [Self::Daily, Self::Weekly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// Topic exchange routing key the report is delivered under
+    pub fn routing_key(&self) -> &'static str {
+        match self {
+            Self::Daily => "report.daily",
+            Self::Weekly => "report.weekly",
+        }
+    }
+
+    /// Bounds of the last period completed before `now`, days start at midnight UTC and
+    /// weeks on Monday
+    pub fn last_complete(&self, now: i64) -> ReportRange {
+        let today = now - now.rem_euclid(SECONDS_PER_DAY);
+
+        let (days, period_end) = match self {
+            Self::Daily => (1, today),
+            Self::Weekly => {
+                let weekday = (now.div_euclid(SECONDS_PER_DAY) + EPOCH_WEEKDAY).rem_euclid(7);
+                (7, today - weekday * SECONDS_PER_DAY)
+            }
+        };
+
+        ReportRange {
+            period_start: period_end - days * SECONDS_PER_DAY,
+            period_end,
+        }
+    }
+}
+
+/// Reported time range in unix seconds, the start is inclusive and the end exclusive
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ReportRange {
+    pub period_start: i64,
+    pub period_end: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnitLogins {
+    pub unit: String,
+    pub logins: u64,
+    pub users: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoginSummary {
+    pub total: u64,
+    pub failed: u64,
+    pub units: Vec<UnitLogins>,
+}
+
+/// Hour with more failed logins than the configured threshold
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FailedLoginSpike {
+    pub hour: i64,
+    pub failed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermissionChange {
+    pub occurred_at: i64,
+    pub actor: Option<String>,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub action: AuditAction,
+    #[serde(default)]
+    pub changed_fields: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EntityChanges {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub changes: u64,
+}
+
+/// Aggregate of the audit records of one day or week
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditReport {
+    pub id: String,
+    pub period: ReportPeriod,
+    #[serde(flatten)]
+    pub range: ReportRange,
+    pub generated_at: i64,
+    pub logins: LoginSummary,
+    pub failed_login_spikes: Vec<FailedLoginSpike>,
+    pub permission_changes: Vec<PermissionChange>,
+    pub top_entities: Vec<EntityChanges>,
+}
+
+/// Stored report without its content, as listed
+#[derive(Serialize, Deserialize)]
+pub struct ReportSummary {
+    pub id: String,
+    pub period: ReportPeriod,
+    #[serde(flatten)]
+    pub range: ReportRange,
+    pub generated_at: i64,
+}
+
+impl AuditReport {
+    /// Report ids are derived from the period, so every period is reported once
+    pub fn id(period: ReportPeriod, range: &ReportRange) -> String {
+        format!("{}-{}", period.as_str(), range.period_start)
+    }
+
+    /// Self-contained printable page, styled for paper as well as for screens
+    pub fn to_html(&self) -> String {
+        let title = format!(
+            "{} audit report {} – {}",
+            capitalize(self.period.as_str()),
+            format_date(self.range.period_start),
+            format_date(self.range.period_end - 1),
+        );
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n<style>{REPORT_STYLE}</style>\n</head>\n<body>\n\
+             <h1>{title}</h1>\n<p class=\"meta\">Generated {}</p>\n",
+            format_time(self.generated_at),
+            title = escape_html(&title),
+        );
+
+        let _ = write!(
+            html,
+            "<h2>Logins</h2>\n<p>{} successful, {} failed</p>\n",
+            self.logins.total, self.logins.failed
+        );
+        html_table(
+            &mut html,
+            &["Unit", "Logins", "Users"],
+            self.logins.units.iter().map(|unit| {
+                vec![
+                    unit.unit.clone(),
+                    unit.logins.to_string(),
+                    unit.users.to_string(),
+                ]
+            }),
+        );
+
+        html.push_str("<h2>Failed login spikes</h2>\n");
+        html_table(
+            &mut html,
+            &["Hour", "Failed logins"],
+            self.failed_login_spikes
+                .iter()
+                .map(|spike| vec![format_time(spike.hour), spike.failed.to_string()]),
+        );
+
+        html.push_str("<h2>Permission changes</h2>\n");
+        html_table(
+            &mut html,
+            &["Time", "Actor", "Entity", "Action", "Fields"],
+            self.permission_changes.iter().map(|change| {
+                vec![
+                    format_time(change.occurred_at),
+                    change.actor.clone().unwrap_or_default(),
+                    format!("{}:{}", change.entity_type, change.entity_id),
+                    change.action.as_str().to_string(),
+                    change.changed_fields.join(", "),
+                ]
+            }),
+        );
+
+        html.push_str("<h2>Most modified entities</h2>\n");
+        html_table(
+            &mut html,
+            &["Entity", "Changes"],
+            self.top_entities.iter().map(|entity| {
+                vec![
+                    format!("{}:{}", entity.entity_type, entity.entity_id),
+                    entity.changes.to_string(),
+                ]
+            }),
+        );
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+const REPORT_STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#111}\
+    h1{font-size:1.5em}h2{font-size:1.2em;margin-top:1.5em}.meta{color:#555}\
+    table{border-collapse:collapse;width:100%}th,td{border:1px solid #999;padding:.3em .5em;\
+    text-align:left}th{background:#eee}tr{break-inside:avoid}\
+    @media print{body{margin:0}h2{break-after:avoid}}";
+
+fn html_table(html: &mut String, headers: &[&str], rows: impl Iterator<Item = Vec<String>>) {
+    let mut rows = rows.peekable();
+    if rows.peek().is_none() {
+        html.push_str("<p>None</p>\n");
+        return;
+    }
+
+    html.push_str("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(html, "<th>{}</th>", escape_html(header));
+    }
+    html.push_str("</tr>\n");
+
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape_html(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,82 @@
+use super::ChainLinkEntity;
+use crate::model::{ArchiveManifest, ExpiredFilter};
+use ::api_util::Error;
+use ::serde::Serialize;
+use ::surrealdb::{Surreal, engine::remote::ws::Client};
+
+pub trait ArchiveRepository {
+    async fn find_expired_events(
+        &self,
+        filter: ExpiredFilter,
+        limit: u32,
+    ) -> Result<Vec<ChainLinkEntity>, Error>;
+    async fn create_archive(
+        &self,
+        manifest: ArchiveManifest,
+        ids: Vec<String>,
+    ) -> Result<(), Error>;
+    async fn find_last_archive(&self) -> Result<Option<ArchiveManifest>, Error>;
+    async fn find_archives(&self) -> Result<Vec<ArchiveManifest>, Error>;
+}
+
+impl ArchiveRepository for Surreal<Client> {
+    async fn find_expired_events(
+        &self,
+        filter: ExpiredFilter,
+        limit: u32,
+    ) -> Result<Vec<ChainLinkEntity>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            filter: ExpiredFilter,
+            limit: u32,
+        }
+
+        let events = self
+            .query(include_str!("../../res/query/archive/expired.surql"))
+            .bind(SqlParams { filter, limit })
+            .await?
+            .take::<Vec<ChainLinkEntity>>(0)?;
+
+        Ok(events)
+    }
+
+    /// Stores the manifest and strips the archived records down to their chain links
+    async fn create_archive(
+        &self,
+        manifest: ArchiveManifest,
+        ids: Vec<String>,
+    ) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            manifest: ArchiveManifest,
+            ids: Vec<String>,
+        }
+
+        self.query(include_str!("../../res/query/archive/create.surql"))
+            .bind(SqlParams { manifest, ids })
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn find_last_archive(&self) -> Result<Option<ArchiveManifest>, Error> {
+        let manifest = self
+            .query(include_str!("../../res/query/archive/last.surql"))
+            .await?
+            .take::<Option<ArchiveManifest>>(0)?;
+
+        Ok(manifest)
+    }
+
+    async fn find_archives(&self) -> Result<Vec<ArchiveManifest>, Error> {
+        let manifests = self
+            .query(include_str!("../../res/query/archive/list.surql"))
+            .await?
+            .take::<Vec<ArchiveManifest>>(0)?;
+
+        Ok(manifests)
+    }
+}
@@ -0,0 +1,121 @@
+use crate::model::{AuditAction, AuditCategory, ChainContent, ChainHead, Checkpoint};
+use ::api_util::Error;
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::{Map, Value};
+use ::surrealdb::{Surreal, engine::remote::ws::Client};
+
+/// Full audit record as linked in the chain, also the line format of archive files
+#[derive(Deserialize, Serialize)]
+pub struct ChainLinkEntity {
+    pub id: String,
+    pub seq: i64,
+    pub prev_hash: String,
+    pub hash: String,
+    pub actor: Option<String>,
+    pub service: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    #[serde(default)]
+    pub category: AuditCategory,
+    pub action: AuditAction,
+    pub occurred_at: i64,
+    pub received_at: i64,
+    #[serde(default)]
+    pub changed_fields: Vec<String>,
+    pub payload: Option<Map<String, Value>>,
+    pub correlation_id: Option<String>,
+    /// Name of the archive holding the record content, archived records keep only
+    /// their place in the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<String>,
+}
+
+impl ChainLinkEntity {
+    pub fn chain_content(&self) -> ChainContent<'_> {
+        ChainContent {
+            id: &self.id,
+            seq: self.seq,
+            actor: self.actor.as_deref(),
+            service: &self.service,
+            entity_type: &self.entity_type,
+            entity_id: &self.entity_id,
+            action: self.action,
+            occurred_at: self.occurred_at,
+            changed_fields: &self.changed_fields,
+            payload: self.payload.as_ref(),
+            correlation_id: self.correlation_id.as_deref(),
+        }
+    }
+}
+
+pub trait ChainRepository {
+    async fn find_chain_head(&self) -> Result<Option<ChainHead>, Error>;
+    async fn find_chain_links(
+        &self,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<ChainLinkEntity>, Error>;
+    async fn create_checkpoint(&self, checkpoint: Checkpoint) -> Result<(), Error>;
+    async fn find_checkpoints(&self) -> Result<Vec<Checkpoint>, Error>;
+    async fn find_last_checkpoint_seq(&self) -> Result<Option<i64>, Error>;
+}
+
+impl ChainRepository for Surreal<Client> {
+    async fn find_chain_head(&self) -> Result<Option<ChainHead>, Error> {
+        let head = self
+            .query(include_str!("../../res/query/chain/head.surql"))
+            .await?
+            .take::<Option<ChainHead>>(0)?;
+
+        Ok(head)
+    }
+
+    async fn find_chain_links(
+        &self,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<ChainLinkEntity>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            after_seq: i64,
+            limit: u32,
+        }
+
+        let links = self
+            .query(include_str!("../../res/query/chain/links.surql"))
+            .bind(SqlParams { after_seq, limit })
+            .await?
+            .take::<Vec<ChainLinkEntity>>(0)?;
+
+        Ok(links)
+    }
+
+    async fn create_checkpoint(&self, checkpoint: Checkpoint) -> Result<(), Error> {
+        self.query(include_str!(
+            "../../res/query/chain/checkpoint_create.surql"
+        ))
+        .bind(checkpoint)
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+
+    async fn find_checkpoints(&self) -> Result<Vec<Checkpoint>, Error> {
+        let checkpoints = self
+            .query(include_str!("../../res/query/chain/checkpoints.surql"))
+            .await?
+            .take::<Vec<Checkpoint>>(0)?;
+
+        Ok(checkpoints)
+    }
+
+    async fn find_last_checkpoint_seq(&self) -> Result<Option<i64>, Error> {
+        let seq = self
+            .query(include_str!("../../res/query/chain/checkpoint_last.surql"))
+            .await?
+            .take::<Option<i64>>(0)?;
+
+        Ok(seq)
+    }
+}
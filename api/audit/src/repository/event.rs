@@ -0,0 +1,195 @@
+use crate::model::{
+    AuditAction, AuditCategory, AuditCursor, AuditEventFilter, ChainContent, SortOrder,
+};
+use ::api_util::Error;
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::{Map, Value};
+use ::surrealdb::{Connection, Surreal};
+
+#[derive(Serialize)]
+pub struct AuditEventEntity {
+    pub message_id: String,
+    pub seq: i64,
+    pub prev_hash: String,
+    pub hash: String,
+    pub actor: Option<String>,
+    pub service: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub category: AuditCategory,
+    pub action: AuditAction,
+    pub occurred_at: i64,
+    pub changed_fields: Vec<String>,
+    pub payload: Option<Map<String, Value>>,
+    pub correlation_id: Option<String>,
+}
+
+impl AuditEventEntity {
+    pub fn chain_content(&self) -> ChainContent<'_> {
+        ChainContent {
+            id: &self.message_id,
+            seq: self.seq,
+            actor: self.actor.as_deref(),
+            service: &self.service,
+            entity_type: &self.entity_type,
+            entity_id: &self.entity_id,
+            action: self.action,
+            occurred_at: self.occurred_at,
+            changed_fields: &self.changed_fields,
+            payload: self.payload.as_ref(),
+            correlation_id: self.correlation_id.as_deref(),
+        }
+    }
+
+    /// The record as read back, `received_at` is only known to the database and
+    /// approximated by the caller
+    pub fn to_record(&self, received_at: i64) -> AuditEventRecord {
+        AuditEventRecord {
+            id: self.message_id.clone(),
+            seq: self.seq,
+            actor: self.actor.clone(),
+            service: self.service.clone(),
+            entity_type: self.entity_type.clone(),
+            entity_id: self.entity_id.clone(),
+            category: self.category,
+            action: self.action,
+            occurred_at: self.occurred_at,
+            received_at,
+            changed_fields: self.changed_fields.clone(),
+            payload: self.payload.clone(),
+            correlation_id: self.correlation_id.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ParkedEventEntity {
+    pub message_id: Option<String>,
+    pub routing_key: String,
+    pub service: Option<String>,
+    pub correlation_id: Option<String>,
+    pub reason: String,
+    pub data: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AuditEventRecord {
+    pub id: String,
+    pub seq: i64,
+    pub actor: Option<String>,
+    pub service: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    #[serde(default)]
+    pub category: AuditCategory,
+    pub action: AuditAction,
+    pub occurred_at: i64,
+    pub received_at: i64,
+    #[serde(default)]
+    pub changed_fields: Vec<String>,
+    pub payload: Option<Map<String, Value>>,
+    pub correlation_id: Option<String>,
+}
+
+pub trait EventRepository {
+    async fn create_audit_event(&self, event: AuditEventEntity) -> Result<bool, Error>;
+    async fn park_event(&self, event: ParkedEventEntity) -> Result<(), Error>;
+    async fn find_audit_events(
+        &self,
+        filter: AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        order: SortOrder,
+        limit: u32,
+    ) -> Result<Vec<AuditEventRecord>, Error>;
+    async fn find_audit_events_after(
+        &self,
+        filter: AuditEventFilter,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<AuditEventRecord>, Error>;
+}
+
+/// Generic over the engine, so that records can be stored in an in-memory database too
+impl<C: Connection> EventRepository for Surreal<C> {
+    /// Redelivered messages are ignored, the record id is the AMQP message id.
+    /// Returns whether the event was stored.
+    async fn create_audit_event(&self, event: AuditEventEntity) -> Result<bool, Error> {
+        let created = self
+            .query(include_str!("../../res/query/event/create.surql"))
+            .bind(event)
+            .await?
+            .take::<Option<bool>>(0)?
+            .unwrap_or(false);
+
+        Ok(created)
+    }
+
+    async fn park_event(&self, event: ParkedEventEntity) -> Result<(), Error> {
+        self.query(include_str!("../../res/query/event/park.surql"))
+            .bind(event)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn find_audit_events(
+        &self,
+        filter: AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        order: SortOrder,
+        limit: u32,
+    ) -> Result<Vec<AuditEventRecord>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            filter: AuditEventFilter,
+            cursor: Option<AuditCursor>,
+            limit: u32,
+        }
+
+        let query = include_str!("../../res/query/event/search.surql")
+            .replace("$direction", order.direction())
+            .replace("$after", order.after());
+
+        let events = self
+            .query(query)
+            .bind(SqlParams {
+                filter,
+                cursor,
+                limit,
+            })
+            .await?
+            .take::<Vec<AuditEventRecord>>(0)?;
+
+        Ok(events)
+    }
+
+    /// Records stored after `after_seq` in chain order
+    async fn find_audit_events_after(
+        &self,
+        filter: AuditEventFilter,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<AuditEventRecord>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            filter: AuditEventFilter,
+            after_seq: i64,
+            limit: u32,
+        }
+
+        let events = self
+            .query(include_str!("../../res/query/event/stream.surql"))
+            .bind(SqlParams {
+                filter,
+                after_seq,
+                limit,
+            })
+            .await?
+            .take::<Vec<AuditEventRecord>>(0)?;
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::model::ChangeSet;
+use ::api_util::Error;
+use ::serde::Serialize;
+use ::surrealdb::{Connection, Surreal};
+
+pub trait ChangeFeedRepository {
+    async fn find_changes(
+        &self,
+        table: &str,
+        since: i64,
+        limit: u32,
+    ) -> Result<Vec<ChangeSet>, Error>;
+    async fn find_feed_cursor(&self, table: &str) -> Result<Option<i64>, Error>;
+    async fn save_feed_cursor(&self, table: &str, versionstamp: i64) -> Result<(), Error>;
+}
+
+/// Generic over the engine, so that feeds can be tailed from an in-memory database too
+impl<C: Connection> ChangeFeedRepository for Surreal<C> {
+    /// Change sets from `since` on. `SHOW CHANGES` accepts no parameters, so the table
+    /// name is formatted into the query and must be a plain identifier.
+    async fn find_changes(
+        &self,
+        table: &str,
+        since: i64,
+        limit: u32,
+    ) -> Result<Vec<ChangeSet>, Error> {
+        let is_identifier = !table.is_empty()
+            && table
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '_');
+        if !is_identifier {
+            return Err(Error::Unknown(
+                "change feed table must be a plain identifier",
+            ));
+        }
+
+        // The JSON conversion renders record ids as `table:key` strings
+        let changes = self
+            .query(format!(
+                "SHOW CHANGES FOR TABLE {table} SINCE {since} LIMIT {limit};"
+            ))
+            .await?
+            .take::<surrealdb::Value>(0)?
+            .into_inner()
+            .into_json();
+
+        Ok(serde_json::from_value(changes)?)
+    }
+
+    async fn find_feed_cursor(&self, table: &str) -> Result<Option<i64>, Error> {
+        let versionstamp = self
+            .query(include_str!("../../res/query/feed/cursor.surql"))
+            .bind(("table", table.to_string()))
+            .await?
+            .take::<Option<i64>>(0)?;
+
+        Ok(versionstamp)
+    }
+
+    async fn save_feed_cursor(&self, table: &str, versionstamp: i64) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            table: String,
+            versionstamp: i64,
+        }
+
+        self.query(include_str!("../../res/query/feed/cursor_save.surql"))
+            .bind(SqlParams {
+                table: table.to_string(),
+                versionstamp,
+            })
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use ::api_util::Error;
+use ::serde::Serialize;
+use ::surrealdb::{Connection, Surreal};
+
+/// Named leases letting one replica at a time run a periodic job
+pub trait LeaseRepository {
+    async fn acquire_lease(&self, name: &str, holder: &str, duration: u64) -> Result<bool, Error>;
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<(), Error>;
+}
+
+impl<C: Connection> LeaseRepository for Surreal<C> {
+    /// Takes or renews the lease for `duration` seconds, `false` while another holder
+    /// has it
+    async fn acquire_lease(&self, name: &str, holder: &str, duration: u64) -> Result<bool, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            name: String,
+            holder: String,
+            duration: u64,
+        }
+
+        let acquired = self
+            .query(include_str!("../../res/query/lease/acquire.surql"))
+            .bind(SqlParams {
+                name: name.to_string(),
+                holder: holder.to_string(),
+                duration,
+            })
+            .await?
+            .take::<Option<bool>>(0)?;
+
+        Ok(acquired.unwrap_or(false))
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<(), Error> {
+        self.query(include_str!("../../res/query/lease/release.surql"))
+            .bind(("name", name.to_string()))
+            .bind(("holder", holder.to_string()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+mod archive;
+mod chain;
+mod event;
+mod feed;
+mod lease;
+mod report;
+
+pub use self::{
+    archive::*,
+    chain::*,
+    event::*,
+    feed::*,
+    lease::*,
+    report::*,
+};
\ No newline at end of file
@@ -0,0 +1,196 @@
+use crate::model::{
+    AuditReport, EntityChanges, PermissionChange, ReportPeriod, ReportRange, ReportSummary,
+};
+use ::api_util::Error;
+use ::serde::{Deserialize, Serialize};
+use ::surrealdb::{Surreal, engine::remote::ws::Client};
+
+#[derive(Deserialize)]
+pub struct ActorLogins {
+    pub actor: String,
+    pub logins: u64,
+}
+
+#[derive(Deserialize)]
+pub struct HourlyFailedLogins {
+    pub hour: i64,
+    pub failed: u64,
+}
+
+#[derive(Deserialize)]
+pub struct UserUnits {
+    pub user_id: String,
+    #[serde(default)]
+    pub units: Vec<String>,
+}
+
+pub trait ReportRepository {
+    async fn find_logins_by_actor(&self, range: ReportRange) -> Result<Vec<ActorLogins>, Error>;
+    async fn find_failed_logins_by_hour(
+        &self,
+        range: ReportRange,
+    ) -> Result<Vec<HourlyFailedLogins>, Error>;
+    async fn find_permission_changes(
+        &self,
+        range: ReportRange,
+        entity_types: Vec<String>,
+        limit: u32,
+    ) -> Result<Vec<PermissionChange>, Error>;
+    async fn find_top_entities(
+        &self,
+        range: ReportRange,
+        limit: u32,
+    ) -> Result<Vec<EntityChanges>, Error>;
+    async fn find_user_units(&self, user_ids: Vec<String>) -> Result<Vec<UserUnits>, Error>;
+    async fn create_report(&self, report: AuditReport) -> Result<bool, Error>;
+    async fn find_report(&self, id: impl Into<String>) -> Result<Option<AuditReport>, Error>;
+    async fn find_reports(
+        &self,
+        period: Option<ReportPeriod>,
+        limit: u32,
+    ) -> Result<Vec<ReportSummary>, Error>;
+}
+
+impl ReportRepository for Surreal<Client> {
+    async fn find_logins_by_actor(&self, range: ReportRange) -> Result<Vec<ActorLogins>, Error> {
+        let logins = self
+            .query(include_str!("../../res/query/report/logins.surql"))
+            .bind(range)
+            .await?
+            .take::<Vec<ActorLogins>>(0)?;
+
+        Ok(logins)
+    }
+
+    async fn find_failed_logins_by_hour(
+        &self,
+        range: ReportRange,
+    ) -> Result<Vec<HourlyFailedLogins>, Error> {
+        let failed = self
+            .query(include_str!("../../res/query/report/failed_logins.surql"))
+            .bind(range)
+            .await?
+            .take::<Vec<HourlyFailedLogins>>(0)?;
+
+        Ok(failed)
+    }
+
+    async fn find_permission_changes(
+        &self,
+        range: ReportRange,
+        entity_types: Vec<String>,
+        limit: u32,
+    ) -> Result<Vec<PermissionChange>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            range: ReportRange,
+            entity_types: Vec<String>,
+            limit: u32,
+        }
+
+        let changes = self
+            .query(include_str!(
+                "../../res/query/report/permission_changes.surql"
+            ))
+            .bind(SqlParams {
+                range,
+                entity_types,
+                limit,
+            })
+            .await?
+            .take::<Vec<PermissionChange>>(0)?;
+
+        Ok(changes)
+    }
+
+    async fn find_top_entities(
+        &self,
+        range: ReportRange,
+        limit: u32,
+    ) -> Result<Vec<EntityChanges>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            #[serde(flatten)]
+            range: ReportRange,
+            limit: u32,
+        }
+
+        let entities = self
+            .query(include_str!("../../res/query/report/top_entities.surql"))
+            .bind(SqlParams { range, limit })
+            .await?
+            .take::<Vec<EntityChanges>>(0)?;
+
+        Ok(entities)
+    }
+
+    /// Names of the groups each user belongs to, read from the access database
+    async fn find_user_units(&self, user_ids: Vec<String>) -> Result<Vec<UserUnits>, Error> {
+        let units = self
+            .query(include_str!("../../res/query/report/user_units.surql"))
+            .bind(("user_ids", user_ids))
+            .await?
+            .take::<Vec<UserUnits>>(0)?;
+
+        Ok(units)
+    }
+
+    /// Returns whether the report was stored, a period already reported is left as is
+    async fn create_report(&self, report: AuditReport) -> Result<bool, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            id: String,
+            period: ReportPeriod,
+            #[serde(flatten)]
+            range: ReportRange,
+            generated_at: i64,
+            report: AuditReport,
+        }
+
+        let created = self
+            .query(include_str!("../../res/query/report/create.surql"))
+            .bind(SqlParams {
+                id: report.id.clone(),
+                period: report.period,
+                range: report.range,
+                generated_at: report.generated_at,
+                report,
+            })
+            .await?
+            .take::<Option<bool>>(0)?
+            .unwrap_or(false);
+
+        Ok(created)
+    }
+
+    async fn find_report(&self, id: impl Into<String>) -> Result<Option<AuditReport>, Error> {
+        let report = self
+            .query(include_str!("../../res/query/report/get.surql"))
+            .bind(("id", id.into()))
+            .await?
+            .take::<Option<AuditReport>>(0)?;
+
+        Ok(report)
+    }
+
+    async fn find_reports(
+        &self,
+        period: Option<ReportPeriod>,
+        limit: u32,
+    ) -> Result<Vec<ReportSummary>, Error> {
+        #[derive(Serialize)]
+        struct SqlParams {
+            period: Option<ReportPeriod>,
+            limit: u32,
+        }
+
+        let reports = self
+            .query(include_str!("../../res/query/report/list.surql"))
+            .bind(SqlParams { period, limit })
+            .await?
+            .take::<Vec<ReportSummary>>(0)?;
+
+        Ok(reports)
+    }
+}
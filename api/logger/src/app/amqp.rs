@@ -1,12 +1,110 @@
-use crate::POOL;
-use ::api_util::amqp::{DeliveryExt, DeliveryResult};
+use super::pool::{QUARANTINE_DIR, host_dir};
+use crate::{CONFIG, LIVE, POOL, SINKS};
+use ::api_util::{
+    amqp::{DeliveryExt, DeliveryResult},
+    log::{self, redact_record, warn},
+    log_record::LogRecord,
+};
+use ::chrono::{SecondsFormat, Utc};
+use ::serde_json::Map;
+use ::std::{collections::BTreeMap, sync::Arc};
 
+const LOG_QUARANTINED_METRIC_NAME: &str = "log_records_quarantined_total";
+/// Field of quarantined records keeping the service their source claimed to be
+const CLAIMED_SERVICE_FIELD: &str = "claimed_service";
+
+/// Control messages of the `logger.broadcast` queue, they set the directives of the
+/// logger's own file log
+pub async fn broadcast_consumer(delivery: DeliveryResult) {
+    let Ok(Some(delivery)) = delivery else { return };
+
+    log::handle_log_control(CONFIG.name, &delivery);
+
+    delivery.confirm()
+}
+
+/// Deliveries carry batches of JSON lines, each line is checked to be a record before it
+/// is appended to the log of the service, queued on the forwarding sinks and then handed
+/// to the live tails.
+///
+/// The `app_id` of the delivery names the service, records from sources which are not
+/// registered are kept apart in the quarantine directory, attributed to it rather than
+/// the service they claim, and never forwarded nor tailed.
 pub async fn amqp_consumer(delivery: DeliveryResult) {
     let Ok(Some(delivery)) = delivery else { return };
 
     let app_id = delivery.app_id();
+    let known = POOL.is_known(app_id);
+
+    let mut hosts = BTreeMap::<String, Vec<u8>>::new();
+    let mut records = Vec::new();
+    for line in delivery
+        .extract_str()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+    {
+        let mut record = serde_json::from_str::<LogRecord>(line)
+            .unwrap_or_else(|err| text_record(app_id, line, err));
+        if known {
+            record.service = app_id.to_string();
+        } else {
+            record.service = QUARANTINE_DIR.to_string();
+            record
+                .fields
+                .insert(CLAIMED_SERVICE_FIELD.to_string(), app_id.into());
+        }
+        // Producers scrub their records, text lines and older builds are scrubbed here
+        redact_record(&mut record);
 
-    POOL.write(&app_id, &delivery.data).await.ok();
+        let data = hosts.entry(host_dir(&record.host).to_string()).or_default();
+        if serde_json::to_writer(&mut *data, &record).is_ok() {
+            data.push(b'\n');
+        }
+        records.push(Arc::new(record));
+    }
+
+    let service = if known {
+        app_id
+    } else {
+        warn!("{app_id:?} is not a registered service, its log records are quarantined");
+        metrics::counter!(LOG_QUARANTINED_METRIC_NAME).increment(records.len() as u64);
+        QUARANTINE_DIR
+    };
+
+    if CONFIG.sinks.file || !known {
+        for (host, data) in hosts {
+            if let Err(err) = POOL.write(service, &host, &data).await {
+                warn!("'{service}/{host}' writing log records: {err}");
+            }
+        }
+    }
+
+    if known {
+        SINKS.dispatch(&records);
+    }
+
+    if known && LIVE.receiver_count() > 0 {
+        for record in records {
+            let _ = LIVE.send(record);
+        }
+    }
 
     delivery.confirm()
 }
+
+/// Services still shipping formatted text keep their lines, wrapped into records
+fn text_record(app_id: &str, line: &str, err: serde_json::Error) -> LogRecord {
+    warn!("{app_id:?} log record is not valid JSON: {err}");
+
+    LogRecord {
+        timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        level: "INFO".to_string(),
+        target: app_id.to_string(),
+        message: line.to_string(),
+        fields: Map::new(),
+        spans: Vec::new(),
+        service: app_id.to_string(),
+        host: String::new(),
+        trace_id: None,
+    }
+}
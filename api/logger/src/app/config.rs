@@ -0,0 +1,220 @@
+use ::api_util::env;
+use ::chrono::{DateTime, Utc};
+use ::std::collections::HashMap;
+
+/// When the file of a service is closed for a new one, on top of its size limit
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl Rotation {
+    fn parse(rotation: &str) -> Option<Self> {
+        match rotation.trim() {
+            "hourly" => Some(Self::Hourly),
+            "daily" => Some(Self::Daily),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// Period a file written at `now` belongs to, `None` when files only rotate by size
+    pub fn period(self, now: DateTime<Utc>) -> Option<String> {
+        match self {
+            Self::Hourly => Some(now.format("%Y-%m-%dT%H").to_string()),
+            Self::Daily => Some(now.format("%Y-%m-%d").to_string()),
+            Self::Never => None,
+        }
+    }
+}
+
+/// Compression of the closed files
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn parse(compression: &str) -> Option<Self> {
+        match compression.trim() {
+            "none" => Some(Self::None),
+            "gzip" => Some(Self::Gzip),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Rotation and retention of the log files of one service
+#[derive(Clone, Copy, Debug)]
+pub struct LogPolicy {
+    pub rotation: Rotation,
+    /// Size in bytes from which a file is closed for a new one, `0` disables it
+    pub max_file_size: u64,
+    pub compression: Compression,
+    /// Days files are kept, `0` keeps them until the disk budget evicts them
+    pub retention_days: u64,
+}
+
+impl LogPolicy {
+    /// Applies `key:value` overrides separated by `;`, unknown keys and values are ignored
+    fn with_overrides(mut self, overrides: &str) -> Self {
+        for (key, value) in overrides.split(';').filter_map(|rule| rule.split_once(':')) {
+            match key.trim() {
+                "rotation" => self.rotation = Rotation::parse(value).unwrap_or(self.rotation),
+                "max_file_size" => {
+                    self.max_file_size = value.trim().parse().unwrap_or(self.max_file_size)
+                }
+                "compression" => {
+                    self.compression = Compression::parse(value).unwrap_or(self.compression)
+                }
+                "retention_days" => {
+                    self.retention_days = value.trim().parse().unwrap_or(self.retention_days)
+                }
+                _ => (),
+            }
+        }
+
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+}
+
+/// RFC 5424 syslog collector
+pub struct Syslog {
+    pub address: &'static str,
+    pub protocol: SyslogProtocol,
+    pub facility: u8,
+}
+
+/// Loki compatible push endpoint, e.g. `http://loki:3100/loki/api/v1/push`
+pub struct Loki {
+    pub url: &'static str,
+    /// Sent as `X-Scope-OrgID` when set
+    pub tenant: Option<&'static str>,
+}
+
+/// Destinations of the records of registered services, each forwarding sink buffers up to
+/// `queue_capacity` records and retries a failed batch `retry_attempts` times
+pub struct Sinks {
+    /// Rolling files under `LOGS_DIR`, quarantined records are written there regardless
+    pub file: bool,
+    pub syslog: Option<Syslog>,
+    pub loki: Option<Loki>,
+    pub queue_capacity: usize,
+    pub batch_size: usize,
+    pub retry_attempts: u32,
+    /// First delay in milliseconds between two attempts, doubled after each of them
+    pub retry_delay: u64,
+}
+
+pub struct AppConfig {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub logs_dir: &'static str,
+    /// Services allowed to ship their logs, other sources are quarantined
+    pub services: Vec<&'static str>,
+    pub policy: LogPolicy,
+    pub service_policies: HashMap<&'static str, LogPolicy>,
+    /// Bytes all log files may use together, `0` disables the budget
+    pub disk_budget: u64,
+    pub maintenance_interval: u64,
+    pub sinks: Sinks,
+}
+
+impl AppConfig {
+    pub fn new() -> Self {
+        let policy = LogPolicy {
+            rotation: Rotation::parse(env::get_var_or_default("LOGGER_ROTATION", "daily"))
+                .unwrap_or(Rotation::Daily),
+            max_file_size: env::get_var_or_default("LOGGER_MAX_FILE_SIZE", "0")
+                .parse()
+                .unwrap_or(0),
+            compression: Compression::parse(env::get_var_or_default("LOGGER_COMPRESSION", "gzip"))
+                .unwrap_or(Compression::Gzip),
+            retention_days: env::get_var_or_default("LOGGER_RETENTION_DAYS", "30")
+                .parse()
+                .unwrap_or(30),
+        };
+
+        let enabled = env::get_var_or_default("LOGGER_SINKS", "file")
+            .split(',')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        let sinks = Sinks {
+            file: enabled.contains(&"file"),
+            syslog: enabled.contains(&"syslog").then(|| Syslog {
+                address: env::get_var_or_default("LOGGER_SYSLOG_ADDRESS", "127.0.0.1:514"),
+                protocol: match env::get_var_or_default("LOGGER_SYSLOG_PROTOCOL", "udp") {
+                    "tcp" => SyslogProtocol::Tcp,
+                    _ => SyslogProtocol::Udp,
+                },
+                facility: env::get_var_or_default("LOGGER_SYSLOG_FACILITY", "1")
+                    .parse()
+                    .ok()
+                    .filter(|facility| *facility < 24)
+                    .unwrap_or(1),
+            }),
+            loki: enabled.contains(&"loki").then(|| Loki {
+                url: env::get_var_or_default(
+                    "LOGGER_LOKI_URL",
+                    "http://127.0.0.1:3100/loki/api/v1/push",
+                ),
+                tenant: Some(env::get_var_or_default("LOGGER_LOKI_TENANT", ""))
+                    .filter(|tenant| !tenant.is_empty()),
+            }),
+            queue_capacity: env::get_var_or_default("LOGGER_SINK_QUEUE_CAPACITY", "10000")
+                .parse()
+                .unwrap_or(10000),
+            batch_size: env::get_var_or_default("LOGGER_SINK_BATCH_SIZE", "500")
+                .parse()
+                .unwrap_or(500),
+            retry_attempts: env::get_var_or_default("LOGGER_SINK_RETRY_ATTEMPTS", "5")
+                .parse()
+                .unwrap_or(5),
+            retry_delay: env::get_var_or_default("LOGGER_SINK_RETRY_DELAY_MS", "500")
+                .parse()
+                .unwrap_or(500),
+        };
+
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            logs_dir: env::get_var_or_default("LOGS_DIR", "/logs"),
+            services: env::get_var_or_default("LOGGER_SERVICES", "access,audit,system")
+                .split(',')
+                .map(str::trim)
+                .filter(|service| !service.is_empty())
+                .collect(),
+            policy,
+            service_policies: env::get_var_or_default("LOGGER_SERVICE_POLICIES", "")
+                .split(',')
+                .filter_map(|rule| rule.split_once('='))
+                .map(|(service, overrides)| (service.trim(), policy.with_overrides(overrides)))
+                .collect(),
+            disk_budget: env::get_var_or_default("LOGGER_DISK_BUDGET", "0")
+                .parse()
+                .unwrap_or(0),
+            maintenance_interval: env::get_var_or_default("LOGGER_MAINTENANCE_INTERVAL", "300")
+                .parse()
+                .unwrap_or(300),
+            sinks,
+        }
+    }
+
+    pub fn policy(&self, service: &str) -> LogPolicy {
+        self.service_policies
+            .get(service)
+            .copied()
+            .unwrap_or(self.policy)
+    }
+}
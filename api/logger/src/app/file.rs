@@ -0,0 +1,280 @@
+use super::config::{Compression, LogPolicy};
+use ::api_util::log::error;
+use ::chrono::{NaiveDate, Utc};
+use ::flate2::{read::MultiGzDecoder, write::GzEncoder};
+use ::std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+const LOG_FILE_SUFFIX: &str = ".jsonl";
+const GZIP_SUFFIX: &str = ".jsonl.gz";
+const ZSTD_SUFFIX: &str = ".jsonl.zst";
+const PART_SUFFIX: &str = ".part";
+
+/// How a stored log file is encoded, told by its suffix
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileKind {
+    Plain,
+    Gzip,
+    Zstd,
+}
+
+impl FileKind {
+    /// Day and encoding of a file named `<YYYY-MM-DD>[THH][.<seq>].jsonl[.gz|.zst]`
+    pub fn parse(name: &str) -> Option<(NaiveDate, Self)> {
+        let kind = if name.ends_with(LOG_FILE_SUFFIX) {
+            Self::Plain
+        } else if name.ends_with(GZIP_SUFFIX) {
+            Self::Gzip
+        } else if name.ends_with(ZSTD_SUFFIX) {
+            Self::Zstd
+        } else {
+            return None;
+        };
+        let date = NaiveDate::parse_from_str(name.get(..10)?, "%Y-%m-%d").ok()?;
+
+        Some((date, kind))
+    }
+
+    /// Reads the JSON lines of the file whatever its encoding
+    pub fn open(self, path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+        let file = File::open(path)?;
+
+        Ok(match self {
+            Self::Plain => Box::new(BufReader::new(file)),
+            Self::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+            Self::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::new(file)?)),
+        })
+    }
+}
+
+/// Log file stored under `<service>/<host>/`
+pub struct StoredFile {
+    pub path: PathBuf,
+    pub service: String,
+    pub host: String,
+    pub date: NaiveDate,
+    pub kind: FileKind,
+    pub size: u64,
+}
+
+impl StoredFile {
+    /// Identifies the file under its service and host, a file keeps its key when it is
+    /// compressed
+    pub fn key(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let name = name.strip_suffix(LOG_FILE_SUFFIX).unwrap_or(name);
+        let name = [GZIP_SUFFIX, ZSTD_SUFFIX]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(name);
+
+        format!("{}/{}/{name}", self.service, self.host)
+    }
+}
+
+/// Open log file of one service and host, closed for a new one when its period ends or
+/// it reaches the size limit of the policy
+pub struct RollingFile {
+    dir: PathBuf,
+    policy: LogPolicy,
+    file: File,
+    path: PathBuf,
+    period: Option<String>,
+    seq: u32,
+    size: u64,
+}
+
+impl RollingFile {
+    pub fn open(dir: PathBuf, policy: LogPolicy) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let period = policy.rotation.period(Utc::now());
+        let (file, path, seq, size) = open_next(&dir, period.as_deref(), 0, policy.max_file_size)?;
+
+        Ok(Self {
+            dir,
+            policy,
+            file,
+            path,
+            period,
+            seq,
+            size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let period = self.policy.rotation.period(Utc::now());
+        if period != self.period {
+            self.rotate(period, 0)?;
+        } else if self.policy.max_file_size > 0
+            && self.size > 0
+            && self.size + data.len() as u64 > self.policy.max_file_size
+        {
+            self.rotate(period, self.seq + 1)?;
+        }
+
+        self.file.write_all(data)?;
+        self.size += data.len() as u64;
+
+        Ok(())
+    }
+
+    /// Opens the next file and compresses the closed one in the background
+    fn rotate(&mut self, period: Option<String>, seq: u32) -> io::Result<()> {
+        let (file, path, seq, size) =
+            open_next(&self.dir, period.as_deref(), seq, self.policy.max_file_size)?;
+
+        self.file = file;
+        self.period = period;
+        self.seq = seq;
+        self.size = size;
+        let closed = mem::replace(&mut self.path, path);
+
+        let compression = self.policy.compression;
+        tokio::task::spawn_blocking(move || {
+            if let Err(err) = compress(&closed, compression) {
+                error!("'{}' compressing log file: {err}", closed.display());
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Opens the first file of the period from `seq` on which was neither compressed nor filled
+/// up, files left by a previous run are appended to
+fn open_next(
+    dir: &Path,
+    period: Option<&str>,
+    mut seq: u32,
+    max_size: u64,
+) -> io::Result<(File, PathBuf, u32, u64)> {
+    let period = period.map_or_else(|| Utc::now().format("%Y-%m-%d").to_string(), str::to_string);
+
+    loop {
+        let name = match seq {
+            0 => format!("{period}{LOG_FILE_SUFFIX}"),
+            seq => format!("{period}.{seq:04}{LOG_FILE_SUFFIX}"),
+        };
+        let path = dir.join(&name);
+        let compressed = [GZIP_SUFFIX, ZSTD_SUFFIX].iter().any(|suffix| {
+            dir.join(name.replace(LOG_FILE_SUFFIX, suffix))
+                .try_exists()
+                .unwrap_or(false)
+        });
+        let size = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+
+        if !compressed && (max_size == 0 || size < max_size) {
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            return Ok((file, path, seq, size));
+        }
+
+        seq += 1;
+    }
+}
+
+/// Replaces a closed plain file by its compressed copy and returns the path of the copy.
+///
+/// A file another task is already compressing, or has compressed, is left alone.
+pub fn compress(path: &Path, compression: Compression) -> io::Result<Option<PathBuf>> {
+    let suffix = match compression {
+        Compression::None => return Ok(None),
+        Compression::Gzip => GZIP_SUFFIX,
+        Compression::Zstd => ZSTD_SUFFIX,
+    };
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let target = path.with_file_name(name.replace(LOG_FILE_SUFFIX, suffix));
+    let part = path.with_file_name(format!("{name}{PART_SUFFIX}"));
+
+    let mut input = match File::open(path) {
+        Ok(input) => input,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let output = match OpenOptions::new().write(true).create_new(true).open(&part) {
+        Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let result = match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+            io::copy(&mut input, &mut encoder).and_then(|_| encoder.finish().map(|_| ()))
+        }
+        Compression::Zstd => zstd::stream::copy_encode(&mut input, output, 0),
+        Compression::None => Ok(()),
+    };
+
+    if let Err(err) = result.and_then(|_| fs::rename(&part, &target)) {
+        let _ = fs::remove_file(&part);
+        return Err(err);
+    }
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+        _ => Ok(Some(target)),
+    }
+}
+
+/// Log files under `<path>/<service>/<host>/`
+pub fn stored_files(path: &Path) -> io::Result<Vec<StoredFile>> {
+    let mut files = Vec::new();
+    for service in sub_dirs(path)? {
+        let Some(name) = service.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let service_name = name.to_string();
+
+        for host in sub_dirs(&service)? {
+            let Some(name) = host.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let host_name = name.to_string();
+
+            for entry in fs::read_dir(&host)? {
+                let entry = entry?;
+                let Some((date, kind)) = entry.file_name().to_str().and_then(FileKind::parse)
+                else {
+                    continue;
+                };
+
+                files.push(StoredFile {
+                    path: entry.path(),
+                    service: service_name.clone(),
+                    host: host_name.clone(),
+                    date,
+                    kind,
+                    size: entry.metadata().map_or(0, |metadata| metadata.len()),
+                });
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn sub_dirs(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+
+    Ok(dirs)
+}
@@ -0,0 +1,115 @@
+use super::{
+    config::AppConfig,
+    file::{FileKind, StoredFile, compress, stored_files},
+    pool::Pool,
+};
+use ::api_util::log::error;
+use ::chrono::{Days, Utc};
+use ::std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+const LOG_DISK_USAGE_METRIC_NAME: &str = "log_disk_usage_bytes";
+const LOG_FILES_REMOVED_METRIC_NAME: &str = "log_files_removed_total";
+
+impl Pool {
+    /// Compresses the files left plain by a previous run, removes the files past their
+    /// retention and evicts the oldest ones while the disk budget is exceeded. Returns the
+    /// number of removed files.
+    pub async fn maintain(&self) -> Result<usize, Error> {
+        let cfg = self.cfg();
+        let open_files = self.open_files().await.into_iter().collect::<HashSet<_>>();
+
+        tokio::task::spawn_blocking(move || maintain(cfg, &open_files))
+            .await
+            .map_err(Error::other)?
+    }
+}
+
+fn maintain(cfg: &AppConfig, open_files: &HashSet<PathBuf>) -> Result<usize, Error> {
+    let today = Utc::now().date_naive();
+    let mut removed = 0;
+
+    let mut files = Vec::new();
+    for mut file in stored_files(Path::new(cfg.logs_dir))? {
+        // Plain files of today may still be written by a file logger outside the pool
+        let closed =
+            !open_files.contains(&file.path) && (file.kind != FileKind::Plain || file.date < today);
+        if !closed {
+            files.push((file, false));
+            continue;
+        }
+
+        let policy = cfg.policy(&file.service);
+        if policy.retention_days > 0
+            && today
+                .checked_sub_days(Days::new(policy.retention_days))
+                .is_some_and(|oldest| file.date < oldest)
+        {
+            removed += remove(&file, "retention");
+            continue;
+        }
+
+        if file.kind == FileKind::Plain {
+            match compress(&file.path, policy.compression) {
+                Ok(Some(path)) => {
+                    file.size = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+                    file.kind = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(FileKind::parse)
+                        .map_or(file.kind, |(_, kind)| kind);
+                    file.path = path;
+                }
+                Ok(None) => (),
+                Err(err) => error!("'{}' compressing log file: {err}", file.path.display()),
+            }
+        }
+
+        files.push((file, true));
+    }
+
+    let mut usage = files.iter().map(|(file, _)| file.size).sum::<u64>();
+    if cfg.disk_budget > 0 && usage > cfg.disk_budget {
+        files.sort_by(|(a, _), (b, _)| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+
+        let mut kept = Vec::with_capacity(files.len());
+        for (file, closed) in files {
+            if closed && usage > cfg.disk_budget && remove(&file, "budget") > 0 {
+                usage -= file.size;
+                removed += 1;
+            } else {
+                kept.push((file, closed));
+            }
+        }
+        files = kept;
+    }
+
+    let mut services = BTreeMap::<&str, u64>::new();
+    for (file, _) in &files {
+        *services.entry(&file.service).or_default() += file.size;
+    }
+    for (service, usage) in services {
+        metrics::gauge!(LOG_DISK_USAGE_METRIC_NAME, "service" => service.to_string())
+            .set(usage as f64);
+    }
+
+    Ok(removed)
+}
+
+/// Returns the number of removed files
+fn remove(file: &StoredFile, reason: &'static str) -> usize {
+    match fs::remove_file(&file.path) {
+        Ok(()) => {
+            metrics::counter!(LOG_FILES_REMOVED_METRIC_NAME, "reason" => reason).increment(1);
+            1
+        }
+        Err(err) => {
+            error!("'{}' removing log file: {err}", file.path.display());
+            0
+        }
+    }
+}
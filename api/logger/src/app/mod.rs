@@ -1,2 +1,8 @@
 pub(crate) mod amqp;
-pub(crate) mod pool;
\ No newline at end of file
+pub(crate) mod config;
+mod file;
+mod maintenance;
+pub(crate) mod pool;
+pub(crate) mod router;
+mod search;
+pub(crate) mod sink;
\ No newline at end of file
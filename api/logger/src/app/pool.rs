@@ -1,52 +1,123 @@
-use ::api_util::log::{
-    error,
-    rolling_appender::{RollingFileAppender, Rotation},
-};
+use super::{config::AppConfig, file::RollingFile};
+use ::api_util::log::error;
 use ::std::{
     collections::HashMap,
-    io::{Error, Write},
+    io::{Error, ErrorKind},
+    path::PathBuf,
     sync::Arc,
 };
 use ::tokio::sync::Mutex;
 
+/// Directory of the records whose source is not a registered service
+pub const QUARANTINE_DIR: &str = "_quarantine";
+const MAX_NAME_LENGTH: usize = 64;
+const LOG_BYTES_WRITTEN_METRIC_NAME: &str = "log_bytes_written_total";
+const LOG_WRITE_ERRORS_METRIC_NAME: &str = "log_write_errors_total";
+
+/// Service and host of a rolling file
+type FileKey = (String, String);
+type SharedFile = Arc<Mutex<RollingFile>>;
+
+/// Rolling log files laid out as `<path>/<service>/<host>/<period>.jsonl`, rotated and
+/// compressed according to the policy of each service
 pub struct Pool {
-    path: &'static str,
-    pool: Mutex<HashMap<String, Arc<Mutex<RollingFileAppender>>>>,
+    cfg: &'static AppConfig,
+    pool: Mutex<HashMap<FileKey, SharedFile>>,
 }
 
 impl Pool {
-    pub fn new(path: &'static str) -> Self {
+    pub fn new(cfg: &'static AppConfig) -> Self {
         Self {
-            path,
+            cfg,
             pool: Mutex::new(HashMap::new()),
         }
     }
 
-    pub async fn write(&self, filename: &str, data: &[u8]) -> Result<(), Error> {
+    pub fn cfg(&self) -> &'static AppConfig {
+        self.cfg
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.cfg.logs_dir
+    }
+
+    /// Whether `service` is registered to ship its logs
+    pub fn is_known(&self, service: &str) -> bool {
+        service != QUARANTINE_DIR && is_valid_name(service) && self.cfg.services.contains(&service)
+    }
+
+    /// Appends the records of one service and host, both have to be plain file names
+    pub async fn write(&self, service: &str, host: &str, data: &[u8]) -> Result<(), Error> {
+        if !is_valid_name(service) || !is_valid_name(host) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("'{service}/{host}' is not a valid log directory"),
+            ));
+        }
+
+        let result = self.write_file(service, host, data).await;
+        match &result {
+            Ok(()) => {
+                metrics::counter!(LOG_BYTES_WRITTEN_METRIC_NAME, "service" => service.to_string())
+                    .increment(data.len() as u64)
+            }
+            Err(err) => {
+                error!("'{service}/{host}' writing log file: {err}");
+                metrics::counter!(LOG_WRITE_ERRORS_METRIC_NAME, "service" => service.to_string())
+                    .increment(1);
+            }
+        }
+
+        result
+    }
+
+    async fn write_file(&self, service: &str, host: &str, data: &[u8]) -> Result<(), Error> {
         let file_rc = {
             let mut pool = self.pool.lock().await;
+            let key = (service.to_string(), host.to_string());
 
-            if pool.contains_key(filename) {
-                pool.get(filename).unwrap().clone()
+            if let Some(file) = pool.get(&key) {
+                file.clone()
             } else {
-                let file_appender = RollingFileAppender::builder()
-                    .filename_prefix(filename)
-                    .filename_suffix("log")
-                    .rotation(Rotation::DAILY)
-                    .max_log_files(30)
-                    .build(self.path)
-                    .expect("failed to initialize rolling file appender");
-                let file = Arc::new(Mutex::new(file_appender));
-                pool.insert(filename.to_string(), file.clone());
+                let file = RollingFile::open(
+                    PathBuf::from(self.cfg.logs_dir).join(service).join(host),
+                    self.cfg.policy(service),
+                )?;
+                let file = Arc::new(Mutex::new(file));
+                pool.insert(key, file.clone());
                 file
             }
         };
 
         let mut file = file_rc.lock().await;
-        if let Err(err) = file.write(data) {
-            error!("failed to write to file: {}", err);
+        file.write(data)
+    }
+
+    /// Files currently written to, they are never compressed nor evicted
+    pub async fn open_files(&self) -> Vec<PathBuf> {
+        let files = self.pool.lock().await.values().cloned().collect::<Vec<_>>();
+
+        let mut paths = Vec::with_capacity(files.len());
+        for file in files {
+            paths.push(file.lock().await.path().to_path_buf());
         }
 
-        Ok(())
+        paths
     }
 }
+
+/// Host directory of a record, names which are not valid fall back to `unknown`
+pub fn host_dir(host: &str) -> &str {
+    if is_valid_name(host) { host } else { "unknown" }
+}
+
+/// Names are used as path components, only ASCII letters, digits, `-`, `_` and inner `.`
+/// are accepted
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && !name.starts_with('.')
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'))
+}
@@ -0,0 +1,12 @@
+use crate::controller::{log, tail};
+use ::api_util::{handler, prometheus, trace};
+use ::axum::{Router, middleware::from_fn, routing::get};
+
+pub fn init_app() -> Router {
+    Router::new()
+        .route("/logs", get(log::find_logs))
+        .route("/logs/tail", get(tail::tail_logs))
+        .route("/healthcheck", get(handler::healthcheck))
+        .route_layer(from_fn(prometheus::track_metrics))
+        .layer(from_fn(trace::trace_context))
+}
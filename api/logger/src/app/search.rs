@@ -0,0 +1,209 @@
+use super::{
+    file::{StoredFile, stored_files},
+    pool::{Pool, host_dir},
+};
+use crate::model::{LogCursor, LogFilter};
+use ::api_util::log_record::LogRecord;
+use ::chrono::{DateTime, Datelike, NaiveTime};
+use ::std::{
+    collections::{BTreeMap, VecDeque},
+    io::{BufRead, Error, ErrorKind},
+    path::Path,
+};
+
+impl Pool {
+    /// Most recent records matching the filter, newest first by the day of their file and
+    /// then their timestamp, each with its position. Only records after `cursor` are
+    /// returned when it is given.
+    ///
+    /// Days are read from the most recent one and the search stops once `limit` records
+    /// were found, a file never holds more than `limit` records in memory.
+    pub async fn search(
+        &self,
+        filter: LogFilter,
+        cursor: Option<LogCursor>,
+        limit: usize,
+    ) -> Result<Vec<(LogCursor, LogRecord)>, Error> {
+        let path = self.path();
+
+        tokio::task::spawn_blocking(move || {
+            search(Path::new(path), &filter, cursor.as_ref(), limit)
+        })
+        .await
+        .map_err(Error::other)?
+    }
+}
+
+fn search(
+    path: &Path,
+    filter: &LogFilter,
+    cursor: Option<&LogCursor>,
+    limit: usize,
+) -> Result<Vec<(LogCursor, LogRecord)>, Error> {
+    let mut days = BTreeMap::<_, Vec<StoredFile>>::new();
+    for file in stored_files(path)? {
+        if filter
+            .service
+            .as_deref()
+            .is_none_or(|service| service == file.service)
+            && filter
+                .host
+                .as_deref()
+                .is_none_or(|host| host_dir(host) == file.host)
+            && overlaps(&file, filter, cursor)
+        {
+            days.entry(file.date).or_default().push(file);
+        }
+    }
+
+    let mut records = Vec::new();
+    for files in days.into_values().rev() {
+        let remaining = limit - records.len();
+
+        let mut day = Vec::new();
+        for file in files {
+            day.extend(read_matching(&file, filter, cursor, remaining)?);
+        }
+        day.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+        day.truncate(remaining);
+        records.extend(day);
+
+        if records.len() >= limit {
+            break;
+        }
+    }
+
+    Ok(records)
+}
+
+/// Whether the day of the file overlaps the time range of the filter and is not newer
+/// than the day of the cursor
+fn overlaps(file: &StoredFile, filter: &LogFilter, cursor: Option<&LogCursor>) -> bool {
+    let start = file.date.and_time(NaiveTime::MIN).and_utc().timestamp();
+    let end = start + 86_400;
+
+    filter.from.is_none_or(|from| from < end)
+        && filter.to.is_none_or(|to| to > start)
+        && cursor.is_none_or(|cursor| file.date.num_days_from_ce() <= cursor.day)
+}
+
+/// Last `limit` records of the file matching the filter and following the cursor, lines
+/// which are not records are skipped and a file compressed in the meantime is read as
+/// empty
+fn read_matching(
+    file: &StoredFile,
+    filter: &LogFilter,
+    cursor: Option<&LogCursor>,
+    limit: usize,
+) -> Result<VecDeque<(LogCursor, LogRecord)>, Error> {
+    let mut records = VecDeque::with_capacity(limit.min(1024));
+    let mut reader = match file.kind.open(&file.path) {
+        Ok(reader) => reader,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(records),
+        Err(err) => return Err(err),
+    };
+
+    let key = file.key();
+    let day = file.date.num_days_from_ce();
+    let mut line = String::new();
+    let mut offset = 0;
+
+    // A truncated or corrupt file is read up to the damage
+    while let Ok(read @ 1..) = reader.read_line(&mut line) {
+        let line_offset = offset;
+        offset += read as u64;
+
+        let record = serde_json::from_str::<LogRecord>(&line);
+        line.clear();
+        let Ok(record) = record else {
+            continue;
+        };
+        if !filter.matches(&record) {
+            continue;
+        }
+
+        let position = LogCursor {
+            day,
+            timestamp: DateTime::parse_from_rfc3339(&record.timestamp)
+                .map_or(0, |timestamp| timestamp.timestamp_micros()),
+            file: key.clone(),
+            offset: line_offset,
+        };
+        if cursor.is_some_and(|cursor| position >= *cursor) {
+            continue;
+        }
+
+        if records.len() == limit {
+            records.pop_front();
+        }
+        records.push_back((position, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::search;
+    use crate::model::LogFilter;
+    use ::serde_json::json;
+    use ::std::{fs, path::Path};
+
+    fn write_day(path: &Path, date: &str, timestamps: &[&str]) {
+        let dir = path.join("access").join("node1");
+        fs::create_dir_all(&dir).unwrap();
+
+        let lines = timestamps
+            .iter()
+            .map(|timestamp| {
+                let record = json!({
+                    "timestamp": timestamp,
+                    "level": "INFO",
+                    "target": "api",
+                    "message": timestamp,
+                    "service": "access",
+                    "host": "node1",
+                });
+                format!("{record}\n")
+            })
+            .collect::<String>();
+        fs::write(dir.join(format!("{date}.jsonl")), lines).unwrap();
+    }
+
+    #[test]
+    fn pages_through_records_written_after_midnight() {
+        let path = std::env::temp_dir().join(format!("log-search-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        write_day(&path, "2026-01-01", &["2026-01-01T23:59:59.700000Z"]);
+        // Written once the day had turned, the first two from a replayed spool
+        write_day(
+            &path,
+            "2026-01-02",
+            &[
+                "2026-01-01T23:59:50.000000Z",
+                "2026-01-01T23:59:59.500000Z",
+                "2026-01-02T00:00:01.000000Z",
+            ],
+        );
+
+        let mut messages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = search(&path, &LogFilter::default(), cursor.as_ref(), 2).unwrap();
+            let Some((last, _)) = page.last() else { break };
+            cursor = Some(last.clone());
+            messages.extend(page.into_iter().map(|(_, record)| record.message));
+        }
+        let _ = fs::remove_dir_all(&path);
+
+        assert_eq!(
+            messages,
+            [
+                "2026-01-02T00:00:01.000000Z",
+                "2026-01-01T23:59:59.500000Z",
+                "2026-01-01T23:59:50.000000Z",
+                "2026-01-01T23:59:59.700000Z",
+            ]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use super::Sink;
+use crate::app::config::Loki;
+use ::api_util::log_record::LogRecord;
+use ::chrono::DateTime;
+use ::serde::Serialize;
+use ::std::{collections::BTreeMap, io::Error, sync::Arc};
+
+const TENANT_HEADER: &str = "X-Scope-OrgID";
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct StreamLabels<'a> {
+    service: &'a str,
+    host: &'a str,
+    level: String,
+}
+
+#[derive(Serialize)]
+struct Stream<'a> {
+    stream: StreamLabels<'a>,
+    /// `[<unix epoch in nanoseconds>, <log line>]`
+    values: Vec<[String; 2]>,
+}
+
+#[derive(Serialize)]
+struct PushRequest<'a> {
+    streams: Vec<Stream<'a>>,
+}
+
+/// Pushes records in the JSON format of Loki's `/loki/api/v1/push`, one stream per
+/// service, host and level with the whole record as the log line
+pub struct LokiSink {
+    cfg: &'static Loki,
+    client: reqwest::Client,
+}
+
+impl LokiSink {
+    pub fn new(cfg: &'static Loki) -> Self {
+        Self {
+            cfg,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl Sink for LokiSink {
+    fn name(&self) -> &'static str {
+        "loki"
+    }
+
+    async fn send(&mut self, records: &[Arc<LogRecord>]) -> Result<(), Error> {
+        let mut streams = BTreeMap::<StreamLabels, Vec<[String; 2]>>::new();
+        for record in records {
+            let timestamp = DateTime::parse_from_rfc3339(&record.timestamp)
+                .ok()
+                .and_then(|timestamp| timestamp.timestamp_nanos_opt())
+                .unwrap_or_default();
+
+            streams
+                .entry(StreamLabels {
+                    service: &record.service,
+                    host: &record.host,
+                    level: record.level.to_ascii_lowercase(),
+                })
+                .or_default()
+                .push([
+                    timestamp.to_string(),
+                    serde_json::to_string(record.as_ref())?,
+                ]);
+        }
+
+        let body = PushRequest {
+            streams: streams
+                .into_iter()
+                .map(|(stream, values)| Stream { stream, values })
+                .collect(),
+        };
+
+        let mut request = self.client.post(self.cfg.url).json(&body);
+        if let Some(tenant) = self.cfg.tenant {
+            request = request.header(TENANT_HEADER, tenant);
+        }
+
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::other)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::run_sink, LokiSink, Sink, TENANT_HEADER};
+    use crate::app::config::{Loki, Sinks};
+    use ::api_util::log_record::LogRecord;
+    use ::serde_json::{Value, json};
+    use ::std::sync::Arc;
+    use ::tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc,
+        task::JoinHandle,
+    };
+
+    /// Request head and body received by the collector
+    struct Request {
+        head: String,
+        body: Value,
+    }
+
+    fn record(level: &str, message: &str) -> Arc<LogRecord> {
+        let record = json!({
+            "timestamp": "2026-01-01T00:00:00.000001Z",
+            "level": level,
+            "target": "api",
+            "message": message,
+            "service": "access",
+            "host": "node1",
+        });
+
+        Arc::new(serde_json::from_value(record).unwrap())
+    }
+
+    fn loki(url: String, tenant: Option<&'static str>) -> &'static Loki {
+        Box::leak(Box::new(Loki {
+            url: url.leak(),
+            tenant,
+        }))
+    }
+
+    /// Local collector answering one request per status, in order
+    async fn collector(statuses: Vec<u16>) -> (String, JoinHandle<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/loki/api/v1/push", listener.local_addr().unwrap());
+
+        let requests = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                let mut received = Vec::new();
+                let mut chunk = [0; 4096];
+                let (head, body) = loop {
+                    let read = stream.read(&mut chunk).await.unwrap();
+                    received.extend_from_slice(&chunk[..read]);
+
+                    let text = String::from_utf8_lossy(&received);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")?
+                                .trim()
+                                .parse::<usize>()
+                                .ok()
+                        })
+                        .unwrap_or_default();
+                    if body.len() >= length {
+                        break (head.to_string(), body.to_string());
+                    }
+                };
+
+                let response = format!(
+                    "HTTP/1.1 {status} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+
+                requests.push(Request {
+                    head,
+                    body: serde_json::from_str(&body).unwrap(),
+                });
+            }
+
+            requests
+        });
+
+        (url, requests)
+    }
+
+    fn sinks(retry_attempts: u32) -> &'static Sinks {
+        Box::leak(Box::new(Sinks {
+            file: false,
+            syslog: None,
+            loki: None,
+            queue_capacity: 10,
+            batch_size: 10,
+            retry_attempts,
+            retry_delay: 1,
+        }))
+    }
+
+    /// Forwards the records as one batch through the retry loop of the sink task
+    async fn forward(sink: LokiSink, retry_attempts: u32, records: Vec<Arc<LogRecord>>) {
+        let (sender, receiver) = mpsc::channel(records.len());
+        for record in records {
+            sender.send(record).await.unwrap();
+        }
+        drop(sender);
+
+        run_sink(sinks(retry_attempts), sink, receiver).await;
+    }
+
+    #[tokio::test]
+    async fn pushes_one_stream_per_service_host_and_level() {
+        let (url, requests) = collector(vec![204]).await;
+        let mut sink = LokiSink::new(loki(url, Some("tenant-1")));
+
+        let records = [
+            record("INFO", "first"),
+            record("ERROR", "failed"),
+            record("info", "second"),
+        ];
+        sink.send(&records).await.unwrap();
+
+        let requests = requests.await.unwrap();
+        let head = requests[0].head.to_ascii_lowercase();
+        assert!(head.starts_with("post /loki/api/v1/push "));
+        assert!(head.contains(&format!("{}: tenant-1", TENANT_HEADER.to_ascii_lowercase())));
+
+        let line = |record: &Arc<LogRecord>| serde_json::to_string(record.as_ref()).unwrap();
+        let nanos = "1767225600000001000";
+        assert_eq!(
+            requests[0].body,
+            json!({
+                "streams": [
+                    {
+                        "stream": { "service": "access", "host": "node1", "level": "error" },
+                        "values": [[nanos, line(&records[1])]],
+                    },
+                    {
+                        "stream": { "service": "access", "host": "node1", "level": "info" },
+                        "values": [[nanos, line(&records[0])], [nanos, line(&records[2])]],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_a_failed_batch() {
+        let (url, requests) = collector(vec![500, 503, 204]).await;
+
+        forward(
+            LokiSink::new(loki(url, None)),
+            2,
+            vec![record("INFO", "kept")],
+        )
+        .await;
+
+        let requests = requests.await.unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests
+                .iter()
+                .all(|request| request.body == requests[0].body)
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_a_batch_once_retries_run_out() {
+        let (url, requests) = collector(vec![500, 500]).await;
+
+        // The task returns once the batch is dropped, the collector saw every attempt
+        forward(
+            LokiSink::new(loki(url, None)),
+            1,
+            vec![record("INFO", "lost")],
+        )
+        .await;
+
+        assert_eq!(requests.await.unwrap().len(), 2);
+    }
+}
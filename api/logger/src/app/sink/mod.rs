@@ -0,0 +1,121 @@
+mod loki;
+mod syslog;
+
+use self::{loki::LokiSink, syslog::SyslogSink};
+use super::config::Sinks;
+use ::api_util::{log::warn, log_record::LogRecord};
+use ::std::{io::Error, sync::Arc};
+use ::tokio::{
+    sync::mpsc::{self, Receiver, Sender, error::TrySendError},
+    time::{Duration, sleep},
+};
+
+const SINK_SENT_METRIC_NAME: &str = "log_sink_records_sent_total";
+const SINK_DROPPED_METRIC_NAME: &str = "log_sink_records_dropped_total";
+const SINK_RETRIES_METRIC_NAME: &str = "log_sink_retries_total";
+
+/// Destination the records are forwarded to
+pub trait Sink: Send + 'static {
+    fn name(&self) -> &'static str;
+    /// Delivers one batch, a failed batch is handed again after a delay
+    fn send(
+        &mut self,
+        records: &[Arc<LogRecord>],
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+struct SinkHandle {
+    name: &'static str,
+    sender: Sender<Arc<LogRecord>>,
+}
+
+/// Forwarding sinks, each one drains its own bounded queue in a background task so that a
+/// slow or unreachable collector never holds back the others
+pub struct SinkSet {
+    handles: Vec<SinkHandle>,
+}
+
+impl SinkSet {
+    /// Spawns a task for every enabled forwarding sink
+    pub fn start(cfg: &'static Sinks) -> Self {
+        let mut handles = Vec::new();
+        if let Some(syslog) = &cfg.syslog {
+            handles.push(spawn_sink(cfg, SyslogSink::new(syslog)));
+        }
+        if let Some(loki) = &cfg.loki {
+            handles.push(spawn_sink(cfg, LokiSink::new(loki)));
+        }
+
+        Self { handles }
+    }
+
+    /// Queues the records on every sink, records a full queue cannot take are dropped
+    pub fn dispatch(&self, records: &[Arc<LogRecord>]) {
+        for handle in &self.handles {
+            for record in records {
+                if let Err(err) = handle.sender.try_send(record.clone()) {
+                    let reason = match err {
+                        TrySendError::Full(_) => "full",
+                        TrySendError::Closed(_) => "closed",
+                    };
+                    metrics::counter!(SINK_DROPPED_METRIC_NAME, "sink" => handle.name, "reason" => reason)
+                        .increment(1);
+                }
+            }
+        }
+    }
+}
+
+fn spawn_sink<S: Sink>(cfg: &'static Sinks, sink: S) -> SinkHandle {
+    let (sender, receiver) = mpsc::channel(cfg.queue_capacity.max(1));
+    let name = sink.name();
+    tokio::spawn(run_sink(cfg, sink, receiver));
+
+    SinkHandle { name, sender }
+}
+
+async fn run_sink<S: Sink>(
+    cfg: &'static Sinks,
+    mut sink: S,
+    mut receiver: Receiver<Arc<LogRecord>>,
+) {
+    let batch_size = cfg.batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+
+    while receiver.recv_many(&mut batch, batch_size).await > 0 {
+        let mut delay = Duration::from_millis(cfg.retry_delay);
+        let mut attempt = 0;
+
+        loop {
+            match sink.send(&batch).await {
+                Ok(()) => {
+                    metrics::counter!(SINK_SENT_METRIC_NAME, "sink" => sink.name())
+                        .increment(batch.len() as u64);
+                    break;
+                }
+                Err(err) if attempt < cfg.retry_attempts => {
+                    warn!(
+                        "'{}' log sink failed, retrying in {delay:?}: {err}",
+                        sink.name()
+                    );
+                    metrics::counter!(SINK_RETRIES_METRIC_NAME, "sink" => sink.name()).increment(1);
+                    sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(err) => {
+                    warn!(
+                        "'{}' log sink failed, {} records dropped: {err}",
+                        sink.name(),
+                        batch.len()
+                    );
+                    metrics::counter!(SINK_DROPPED_METRIC_NAME, "sink" => sink.name(), "reason" => "retry")
+                        .increment(batch.len() as u64);
+                    break;
+                }
+            }
+        }
+
+        batch.clear();
+    }
+}
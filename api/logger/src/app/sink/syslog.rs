@@ -0,0 +1,223 @@
+use super::Sink;
+use crate::app::config::{Syslog, SyslogProtocol};
+use ::api_util::log_record::LogRecord;
+use ::std::{fmt::Write as _, io::Error, sync::Arc};
+use ::tokio::{
+    io::AsyncWriteExt,
+    net::{TcpStream, UdpSocket},
+};
+
+/// Private enterprise number reserved for documentation, RFC 5612
+const SD_ID: &str = "log@32473";
+const NIL: &str = "-";
+const MAX_APP_NAME_LENGTH: usize = 48;
+const MAX_HOSTNAME_LENGTH: usize = 255;
+
+/// Forwards records as RFC 5424 messages, one datagram each over UDP and octet-counted
+/// frames (RFC 6587) over TCP. The TCP connection is reopened after a failure.
+pub struct SyslogSink {
+    cfg: &'static Syslog,
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpStream>,
+}
+
+impl SyslogSink {
+    pub fn new(cfg: &'static Syslog) -> Self {
+        Self {
+            cfg,
+            udp: None,
+            tcp: None,
+        }
+    }
+
+    async fn send_udp(&mut self, messages: &[String]) -> Result<(), Error> {
+        let socket = match &mut self.udp {
+            Some(socket) => socket,
+            None => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket.connect(self.cfg.address).await?;
+                self.udp.insert(socket)
+            }
+        };
+
+        for message in messages {
+            socket.send(message.as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_tcp(&mut self, messages: &[String]) -> Result<(), Error> {
+        let mut frames = Vec::new();
+        for message in messages {
+            frames.extend_from_slice(format!("{} ", message.len()).as_bytes());
+            frames.extend_from_slice(message.as_bytes());
+        }
+
+        let stream = match &mut self.tcp {
+            Some(stream) => stream,
+            None => self.tcp.insert(TcpStream::connect(self.cfg.address).await?),
+        };
+        let result = stream.write_all(&frames).await;
+        if result.is_err() {
+            self.tcp = None;
+        }
+
+        result
+    }
+}
+
+impl Sink for SyslogSink {
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    async fn send(&mut self, records: &[Arc<LogRecord>]) -> Result<(), Error> {
+        let messages = records
+            .iter()
+            .map(|record| format_message(self.cfg.facility, record))
+            .collect::<Vec<_>>();
+
+        match self.cfg.protocol {
+            SyslogProtocol::Udp => self.send_udp(&messages).await,
+            SyslogProtocol::Tcp => self.send_tcp(&messages).await,
+        }
+    }
+}
+
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] MSG`, the fields of the record
+/// follow the message as JSON
+fn format_message(facility: u8, record: &LogRecord) -> String {
+    let severity = match record.level.to_ascii_uppercase().as_str() {
+        "ERROR" => 3,
+        "WARN" => 4,
+        "INFO" => 6,
+        _ => 7,
+    };
+
+    let mut message = format!(
+        "<{}>1 {} {} {} {NIL} {NIL} [{SD_ID} target=\"{}\"",
+        u16::from(facility) * 8 + severity,
+        header_field(&record.timestamp, usize::MAX),
+        header_field(&record.host, MAX_HOSTNAME_LENGTH),
+        header_field(&record.service, MAX_APP_NAME_LENGTH),
+        param_value(&record.target),
+    );
+    if let Some(trace_id) = &record.trace_id {
+        let _ = write!(message, " trace_id=\"{}\"", param_value(trace_id));
+    }
+    message.push_str("] ");
+    message.push_str(&record.message);
+    if !record.fields.is_empty() {
+        let _ = write!(
+            message,
+            " {}",
+            serde_json::Value::Object(record.fields.clone())
+        );
+    }
+
+    message
+}
+
+/// Header fields are printable ASCII without spaces, an empty field is `-`
+fn header_field(value: &str, max_length: usize) -> String {
+    let value = value
+        .chars()
+        .filter(|char| char.is_ascii_graphic())
+        .take(max_length)
+        .collect::<String>();
+
+    if value.is_empty() {
+        NIL.to_string()
+    } else {
+        value
+    }
+}
+
+/// `"`, `\` and `]` are escaped inside structured data parameter values
+fn param_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if matches!(char, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sink, SyslogSink};
+    use crate::app::config::{Syslog, SyslogProtocol};
+    use ::api_util::log_record::LogRecord;
+    use ::serde_json::json;
+    use ::std::sync::Arc;
+    use ::tokio::{
+        io::AsyncReadExt,
+        net::{TcpListener, UdpSocket},
+    };
+
+    const MESSAGE: &str = "<12>1 2026-01-01T00:00:00.000000Z node1 access - - \
+        [log@32473 target=\"api::\\\"auth\\]\" trace_id=\"abc\"] login refused {\"login\":\"alice\"}";
+
+    fn record() -> Arc<LogRecord> {
+        let record = json!({
+            "timestamp": "2026-01-01T00:00:00.000000Z",
+            "level": "WARN",
+            "target": "api::\"auth]",
+            "message": "login refused",
+            "fields": { "login": "alice" },
+            "service": "access",
+            "host": "node 1",
+            "trace_id": "abc",
+        });
+
+        Arc::new(serde_json::from_value(record).unwrap())
+    }
+
+    fn sink(address: String, protocol: SyslogProtocol) -> SyslogSink {
+        SyslogSink::new(Box::leak(Box::new(Syslog {
+            address: address.leak(),
+            protocol,
+            facility: 1,
+        })))
+    }
+
+    #[tokio::test]
+    async fn sends_one_rfc5424_datagram_per_record() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut sink = sink(
+            socket.local_addr().unwrap().to_string(),
+            SyslogProtocol::Udp,
+        );
+
+        sink.send(&[record(), record()]).await.unwrap();
+
+        let mut datagram = [0; 1024];
+        for _ in 0..2 {
+            let len = socket.recv(&mut datagram).await.unwrap();
+            assert_eq!(std::str::from_utf8(&datagram[..len]).unwrap(), MESSAGE);
+        }
+    }
+
+    #[tokio::test]
+    async fn frames_tcp_messages_with_their_octet_count() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut sink = sink(
+            listener.local_addr().unwrap().to_string(),
+            SyslogProtocol::Tcp,
+        );
+
+        sink.send(&[record(), record()]).await.unwrap();
+        drop(sink);
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut frames = String::new();
+        stream.read_to_string(&mut frames).await.unwrap();
+
+        let frame = format!("{} {MESSAGE}", MESSAGE.len());
+        assert_eq!(frames, frame.repeat(2));
+    }
+}
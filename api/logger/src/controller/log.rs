@@ -0,0 +1,86 @@
+use crate::{
+    POOL,
+    model::{LogCursor, LogFilter, LogLevel},
+};
+use ::api_util::{
+    Error,
+    auth::{Capabilities, Claims, SYSTEM_PERMISSION},
+    log_record::LogRecord,
+};
+use ::axum::{Json, extract::Query, response::IntoResponse};
+use ::serde::{Deserialize, Serialize};
+
+const DEFAULT_LOGS_LIMIT: usize = 100;
+const MAX_LOGS_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct FindLogsPayload {
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub level: Option<LogLevel>,
+    #[serde(default)]
+    pub from: Option<i64>,
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct LogsBody {
+    pub items: Vec<LogRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Searches the stored log records, newest first. `level` is the minimum severity and
+/// `to` is exclusive, a page continues from the `next_cursor` of the previous one.
+pub async fn find_logs(
+    claims: Claims<'_>,
+    Query(payload): Query<FindLogsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    claims.has_capabilities(SYSTEM_PERMISSION, Capabilities::VIEW)?;
+
+    let cursor = match payload.cursor {
+        Some(cursor) => {
+            Some(LogCursor::decode(cursor).ok_or(Error::BadRequest("Invalid cursor".into()))?)
+        }
+        None => None,
+    };
+
+    let filter = LogFilter {
+        service: payload.service,
+        host: payload.host,
+        level: payload.level,
+        from: payload.from,
+        to: payload.to,
+        text: payload.text.filter(|text| !text.is_empty()),
+        trace_id: payload.trace_id,
+    };
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_LOGS_LIMIT)
+        .clamp(1, MAX_LOGS_LIMIT);
+
+    // One extra record tells whether another page follows
+    let mut records = POOL.search(filter, cursor, limit + 1).await?;
+
+    let next_cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|(position, _)| position.encode())
+    } else {
+        None
+    };
+    let items = records.into_iter().map(|(_, record)| record).collect();
+
+    Ok(Json(LogsBody { items, next_cursor }))
+}
@@ -0,0 +1,96 @@
+use crate::{
+    LIVE,
+    model::{LogFilter, LogLevel},
+};
+use ::api_util::{
+    Error,
+    auth::{Capabilities, Claims, SYSTEM_PERMISSION},
+    log_record::LogRecord,
+};
+use ::axum::{
+    extract::Query,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use ::chrono::Utc;
+use ::futures::stream;
+use ::serde::Deserialize;
+use ::std::sync::Arc;
+use ::tokio::{
+    sync::broadcast::{Receiver, error::RecvError},
+    time::{Duration, timeout},
+};
+
+#[derive(Deserialize)]
+pub struct TailLogsPayload {
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub level: Option<LogLevel>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+struct TailState {
+    filter: LogFilter,
+    live: Receiver<Arc<LogRecord>>,
+    expires_at: i64,
+}
+
+impl TailState {
+    /// Next written record matching the filter, `None` ends the stream
+    async fn next(&mut self) -> Option<Arc<LogRecord>> {
+        loop {
+            match self.live.recv().await {
+                Ok(record) if self.filter.matches(&record) => return Some(record),
+                Ok(_) | Err(RecvError::Lagged(_)) => (),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Pushes the records matching the filter as Server-Sent Events once they are written.
+///
+/// A subscriber falling behind skips the records it missed, the search covers them.
+/// The stream ends when the token it was opened with expires.
+pub async fn tail_logs(
+    claims: Claims<'_>,
+    Query(payload): Query<TailLogsPayload>,
+) -> Result<impl IntoResponse, Error> {
+    claims.has_capabilities(SYSTEM_PERMISSION, Capabilities::VIEW)?;
+
+    let tail_state = TailState {
+        filter: LogFilter {
+            service: payload.service,
+            host: payload.host,
+            level: payload.level,
+            text: payload.text.filter(|text| !text.is_empty()),
+            trace_id: payload.trace_id,
+            ..Default::default()
+        },
+        live: LIVE.subscribe(),
+        expires_at: claims.exp as i64,
+    };
+
+    let events = stream::unfold(tail_state, |mut tail_state| async move {
+        let expires_in = (tail_state.expires_at - Utc::now().timestamp()).max(0);
+        let next = timeout(Duration::from_secs(expires_in as u64), tail_state.next());
+        let record = next.await.ok()??;
+
+        let event = Event::default()
+            .event("log")
+            .json_data(record.as_ref())
+            .map_err(|_| Error::Unknown("Log record serialization failed"));
+
+        Some((event, tail_state))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
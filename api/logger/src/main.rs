@@ -1,45 +1,93 @@
 mod app;
+mod controller;
+mod model;
 
-use crate::app::{amqp::amqp_consumer, pool::Pool};
+use crate::app::{
+    amqp::{amqp_consumer, broadcast_consumer},
+    config::AppConfig,
+    pool::Pool,
+    router::init_app,
+    sink::SinkSet,
+};
 use ::api_util::{
     Error,
     amqp::{AMQPChannelOptions, ExchangeKind},
     amqp_init,
     console::*,
-    env, log,
+    log,
+    log_record::LogRecord,
     panic::*,
+    server,
     shutdown::*,
 };
-use ::std::sync::LazyLock;
+use ::std::sync::{Arc, LazyLock};
+use ::tokio::{
+    sync::broadcast::{self, Sender},
+    time::{Duration, sleep},
+};
+
+const LIVE_CAPACITY: usize = 1024;
 
-static POOL: LazyLock<Pool> =
-    LazyLock::new(|| Pool::new(env::get_var_or_default("LOGS_DIR", "/logs")));
+static CONFIG: LazyLock<AppConfig> = LazyLock::new(AppConfig::new);
+static POOL: LazyLock<Pool> = LazyLock::new(|| Pool::new(&CONFIG));
+static SINKS: LazyLock<SinkSet> = LazyLock::new(|| SinkSet::start(&CONFIG.sinks));
+static LIVE: LazyLock<Sender<Arc<LogRecord>>> =
+    LazyLock::new(|| broadcast::channel(LIVE_CAPACITY).0);
 
 #[tokio::main]
 async fn main() -> Result<(), Box<Error>> {
     print_banner();
-    set_panic_hook(None);
 
-    let _logger_guard = log::file_logger(
-        env::get_var_or_default("LOGS_DIR", "/logs"),
-        env!("CARGO_PKG_NAME"),
-    );
+    let shutdown_handle = create_shutdown_handle().await;
+    set_panic_hook(Some(shutdown_handle.clone()));
+
+    // Without its own log directory the logger keeps running and logs to stdout
+    let _logger_guard = match log::file_logger(CONFIG.logs_dir, CONFIG.name) {
+        Ok(guard) => Some(guard),
+        Err(err) => {
+            log::stdout_logger();
+            log::error!("'{}' initializing file log: {err}", CONFIG.logs_dir);
+            None
+        }
+    };
+
+    print_service_started(CONFIG.name, CONFIG.version);
+
+    LazyLock::force(&SINKS);
 
-    print_service_started(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let amqp = amqp_init!();
+    amqp.set_delegate(
+        "logger.log",
+        AMQPChannelOptions::default()
+            .with_exchange(ExchangeKind::Topic)
+            .with_routing_key("log.write")
+            .with_durable(),
+        amqp_consumer,
+    )
+    .await?;
+    amqp.set_delegate(
+        "logger.broadcast",
+        AMQPChannelOptions::default()
+            .with_exchange(ExchangeKind::Fanout)
+            .with_durable(),
+        broadcast_consumer,
+    )
+    .await?;
 
-    amqp_init!()
-        .set_delegate(
-            "logger.log",
-            AMQPChannelOptions::default()
-                .with_exchange(ExchangeKind::Topic)
-                .with_routing_key("log.write")
-                .with_durable(),
-            amqp_consumer,
-        )
-        .await?;
+    tokio::spawn(async {
+        let timeout = Duration::from_secs(CONFIG.maintenance_interval);
+        loop {
+            match POOL.maintain().await {
+                Ok(0) => (),
+                Ok(removed) => log::info!("{removed} log files removed"),
+                Err(err) => log::error!("maintaining log files: {err}"),
+            }
+            sleep(timeout).await;
+        }
+    });
 
-    wait_for_shutdown_signals().await;
+    server::start_server(init_app(), shutdown_handle).await;
 
-    print_service_stopped();
+    print_service_stopped().await;
     Ok(())
 }
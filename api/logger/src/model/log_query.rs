@@ -0,0 +1,122 @@
+use ::api_util::log_record::LogRecord;
+use ::base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ::chrono::DateTime;
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::Value;
+
+/// Severity of a record, ordered from the most verbose to the most severe
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Criteria shared by the log search and the live tail.
+///
+/// `level` is the minimum severity, `from` and `to` are unix seconds with `to` exclusive and
+/// `text` is matched case-insensitively against the message, the target and field values.
+#[derive(Clone, Default, Debug)]
+pub struct LogFilter {
+    pub service: Option<String>,
+    pub host: Option<String>,
+    pub level: Option<LogLevel>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub text: Option<String>,
+    pub trace_id: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        self.service
+            .as_deref()
+            .is_none_or(|service| service == record.service)
+            && self.host.as_deref().is_none_or(|host| host == record.host)
+            && self.level.is_none_or(|level| {
+                LogLevel::parse(&record.level).is_some_and(|record_level| record_level >= level)
+            })
+            && self
+                .trace_id
+                .as_deref()
+                .is_none_or(|trace_id| record.trace_id.as_deref() == Some(trace_id))
+            && self.matches_time(record)
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|text| contains_text(record, &text.to_lowercase()))
+    }
+
+    fn matches_time(&self, record: &LogRecord) -> bool {
+        if self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+
+        let Ok(timestamp) = DateTime::parse_from_rfc3339(&record.timestamp) else {
+            return false;
+        };
+        let timestamp = timestamp.timestamp();
+
+        self.from.is_none_or(|from| timestamp >= from) && self.to.is_none_or(|to| timestamp < to)
+    }
+}
+
+/// Position of a stored record. Records are ordered by the day of the file holding them,
+/// in days since the common era, then by their timestamp in unix microseconds, the file
+/// and their offset in its decoded lines, so a page continues exactly after the last
+/// record of the previous one. Records written the day after their timestamp, around
+/// midnight or replayed from a spool, keep the order of the files they are read from.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct LogCursor {
+    pub day: i32,
+    pub timestamp: i64,
+    pub file: String,
+    pub offset: u64,
+}
+
+impl LogCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: impl AsRef<str>) -> Option<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor.as_ref())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    }
+}
+
+fn contains_text(record: &LogRecord, text: &str) -> bool {
+    fn value_contains(value: &Value, text: &str) -> bool {
+        match value {
+            Value::String(value) => value.to_lowercase().contains(text),
+            Value::Array(values) => values.iter().any(|value| value_contains(value, text)),
+            Value::Object(values) => values.values().any(|value| value_contains(value, text)),
+            value => value.to_string().contains(text),
+        }
+    }
+
+    record.message.to_lowercase().contains(text)
+        || record.target.to_lowercase().contains(text)
+        || record
+            .fields
+            .values()
+            .any(|value| value_contains(value, text))
+}
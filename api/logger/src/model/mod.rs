@@ -0,0 +1,3 @@
+mod log_query;
+
+pub use self::log_query::*;
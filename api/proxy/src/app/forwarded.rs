@@ -0,0 +1,62 @@
+use super::tls::ClientCertificate;
+use ::api_util::log::warn;
+use ::axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use ::base64::{Engine, engine::general_purpose::STANDARD};
+use ::std::net::SocketAddr;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_CLIENT_CERT_SUBJECT: &str = "x-client-cert-subject";
+const X_CLIENT_CERT_SERIAL: &str = "x-client-cert-serial";
+
+/// Replaces any client supplied forwarding headers with the values known to the proxy,
+/// so upstream services can trust them: the peer address and the verified client
+/// certificate, if any. The certificate subject is base64 encoded, distinguished names
+/// may hold any UTF-8 while header values are limited to visible ASCII.
+pub async fn set_forwarded_headers(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let certificate = req
+        .extensions()
+        .get::<Option<ClientCertificate>>()
+        .cloned()
+        .flatten();
+    let headers = req.headers_mut();
+
+    set_header(headers, X_FORWARDED_FOR, Some(&addr.ip().to_string()));
+    set_header(
+        headers,
+        X_CLIENT_CERT_SUBJECT,
+        certificate
+            .as_ref()
+            .map(|cert| STANDARD.encode(&cert.subject))
+            .as_deref(),
+    );
+    set_header(
+        headers,
+        X_CLIENT_CERT_SERIAL,
+        certificate.as_ref().map(|cert| cert.serial.as_str()),
+    );
+
+    next.run(req).await
+}
+
+fn set_header(headers: &mut HeaderMap, name: &'static str, value: Option<&str>) {
+    headers.remove(name);
+
+    let Some(value) = value else {
+        return;
+    };
+    match HeaderValue::from_str(value) {
+        Ok(value) => {
+            headers.insert(name, value);
+        }
+        Err(err) => warn!("'{name}' header not forwarded: {err}"),
+    }
+}
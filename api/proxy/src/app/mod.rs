@@ -1,5 +1,8 @@
+mod forwarded;
 mod router;
+mod tls;
 
 pub(crate) use self::{
     router::*,
-};
\ No newline at end of file
+    tls::*,
+};
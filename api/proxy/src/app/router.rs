@@ -1,12 +1,27 @@
-use ::api_util::{env, handler, prometheus};
-use ::axum::{Router, extract::DefaultBodyLimit, http::Method, middleware::from_fn, routing::get};
+use super::forwarded::set_forwarded_headers;
+use ::api_util::{
+    env, handler,
+    log::warn,
+    prometheus,
+    trace::{self, REQUEST_ID_HEADER, TRACEPARENT_HEADER},
+};
+use ::axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    http::{HeaderName, HeaderValue, Method},
+    middleware::from_fn,
+    routing::get,
+};
 use ::axum_reverse_proxy::{RetryLayer, ReverseProxy};
 use ::tower::ServiceBuilder;
-use ::tower_http::{compression::CompressionLayer, cors::CorsLayer};
+use ::tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+};
 
 pub fn init_app() -> Router {
     let cors_layer = CorsLayer::new()
-        .allow_origin(["*".parse().unwrap()])
+        .allow_origin(allowed_origins())
         .allow_methods([
             Method::GET,
             Method::POST,
@@ -14,7 +29,11 @@ pub fn init_app() -> Router {
             Method::DELETE,
             Method::OPTIONS,
         ])
-        .allow_credentials(true);
+        .allow_credentials(true)
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(TRACEPARENT_HEADER),
+        ]);
 
     let compression_layer = CompressionLayer::new().br(true).gzip(true).zstd(true);
 
@@ -23,10 +42,31 @@ pub fn init_app() -> Router {
             "/",
             env::get_var_or_default("ACCESS_URL", "http://access:80"),
         ))
+        .layer(from_fn(set_forwarded_headers))
         .layer(ServiceBuilder::new().layer(RetryLayer::new(3)))
         .route("/healthcheck", get(handler::healthcheck))
         .route_layer(from_fn(prometheus::track_metrics))
         .layer(compression_layer)
         .layer(cors_layer)
         .layer(DefaultBodyLimit::max(104_857_600))
+        .layer(from_fn(trace::trace_context))
+}
+
+/// Origins allowed to make credentialed requests, none unless configured in
+/// `CORS_ALLOWED_ORIGINS` as a comma separated list such as `https://app.example.com`
+fn allowed_origins() -> AllowOrigin {
+    let origins = env::get_var_or_default("CORS_ALLOWED_ORIGINS", "")
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                warn!("ignoring invalid CORS origin: {origin}");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    AllowOrigin::list(origins)
 }
@@ -0,0 +1,293 @@
+use ::axum::{Extension, middleware::AddExtension};
+use ::axum_server::{
+    accept::Accept,
+    tls_rustls::{RustlsAcceptor, RustlsConfig},
+};
+use ::rustls::{
+    RootCertStore, ServerConfig,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+};
+use ::std::{
+    fs::File,
+    io::{self, BufReader},
+    pin::Pin,
+    sync::Arc,
+};
+use ::tokio::io::{AsyncRead, AsyncWrite};
+use ::tokio_rustls::server::TlsStream;
+use ::tower::Layer;
+use ::x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Subject and serial number of a client certificate verified during the TLS handshake
+#[derive(Clone)]
+pub struct ClientCertificate {
+    pub subject: String,
+    pub serial: String,
+}
+
+impl ClientCertificate {
+    fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+
+        Some(Self {
+            subject: certificate.subject().to_string(),
+            serial: certificate
+                .raw_serial()
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect(),
+        })
+    }
+}
+
+/// Builds the TLS configuration, optionally requesting client certificates signed by
+/// the given CA bundle. Clients without a certificate are still accepted.
+pub async fn rustls_config(
+    cert_path: &str,
+    key_path: &str,
+    client_ca_path: Option<&str>,
+) -> io::Result<RustlsConfig> {
+    // Several crypto backends end up in the dependency graph, so rustls cannot pick one itself
+    let _ = ::rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    let Some(client_ca_path) = client_ca_path else {
+        return RustlsConfig::from_pem_file(cert_path, key_path).await;
+    };
+
+    let mut roots = RootCertStore::empty();
+    for certificate in read_certificates(client_ca_path)? {
+        roots.add(certificate).map_err(io::Error::other)?;
+    }
+
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+        .allow_unauthenticated()
+        .build()
+        .map_err(io::Error::other)?;
+
+    let mut config = ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(read_certificates(cert_path)?, read_private_key(key_path)?)
+        .map_err(io::Error::other)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+fn read_certificates(path: &str) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::certs(&mut reader).collect()
+}
+
+fn read_private_key(path: &str) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| io::Error::other("private key not found"))
+}
+
+/// TLS acceptor exposing the verified client certificate to request handlers
+#[derive(Clone)]
+pub struct ClientCertAcceptor {
+    inner: RustlsAcceptor,
+}
+
+impl ClientCertAcceptor {
+    pub fn new(inner: RustlsAcceptor) -> Self {
+        Self { inner }
+    }
+}
+
+impl<I, S> Accept<I, S> for ClientCertAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = AddExtension<S, Option<ClientCertificate>>;
+    type Future = Pin<Box<dyn Future<Output = io::Result<(Self::Stream, Self::Service)>> + Send>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let acceptor = self.inner.clone();
+
+        Box::pin(async move {
+            let (stream, service) = acceptor.accept(stream, service).await?;
+            let certificate = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .and_then(|certificate| ClientCertificate::from_der(certificate));
+
+            Ok((stream, Extension(certificate).layer(service)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClientCertAcceptor, rustls_config};
+    use crate::app::forwarded::set_forwarded_headers;
+    use ::axum::{Router, http::HeaderMap, middleware::from_fn, routing::get};
+    use ::axum_server::tls_rustls::RustlsAcceptor;
+    use ::base64::{Engine, engine::general_purpose::STANDARD};
+    use ::rcgen::{
+        BasicConstraints, Certificate, CertificateParams, DnType, IsCa, Issuer, KeyPair,
+    };
+    use ::rustls::{
+        ClientConfig, RootCertStore,
+        pki_types::{PrivatePkcs8KeyDer, ServerName},
+    };
+    use ::std::{
+        fs,
+        net::{SocketAddr, TcpListener},
+        sync::{
+            Arc,
+            atomic::{AtomicU32, Ordering},
+        },
+    };
+    use ::tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use ::tokio_rustls::TlsConnector;
+
+    const SUBJECT: &str = "CN=Иван Петров, O=Рога и копыта";
+
+    /// Echoes the forwarded certificate headers
+    async fn forwarded(headers: HeaderMap) -> String {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+        };
+
+        format!(
+            "{}\n{}",
+            header("x-client-cert-subject"),
+            header("x-client-cert-serial")
+        )
+    }
+
+    fn signed(params: CertificateParams, issuer: &Issuer<'_, KeyPair>) -> (Certificate, KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let certificate = params.signed_by(&key, issuer).unwrap();
+
+        (certificate, key)
+    }
+
+    /// Starts the proxy TLS stack on a local port, returns the address and the CA
+    async fn serve() -> (SocketAddr, Issuer<'static, KeyPair>, Certificate) {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "test CA");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(params, ca_key);
+
+        let (cert, key) = signed(
+            CertificateParams::new(vec!["localhost".to_string()]).unwrap(),
+            &issuer,
+        );
+
+        static SERVERS: AtomicU32 = AtomicU32::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "proxy-tls-{}-{}",
+            std::process::id(),
+            SERVERS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        fs::write(path("ca.pem"), ca.pem()).unwrap();
+        fs::write(path("cert.pem"), cert.pem()).unwrap();
+        fs::write(path("key.pem"), key.serialize_pem()).unwrap();
+
+        let config = rustls_config(&path("cert.pem"), &path("key.pem"), Some(&path("ca.pem")))
+            .await
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/", get(forwarded))
+            .layer(from_fn(set_forwarded_headers));
+        tokio::spawn(
+            axum_server::from_tcp(listener)
+                .acceptor(ClientCertAcceptor::new(RustlsAcceptor::new(config)))
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()),
+        );
+
+        (addr, issuer, ca)
+    }
+
+    /// Sends a request with forged certificate headers over TLS, returns the echoed headers
+    async fn request(addr: SocketAddr, config: ClientConfig) -> (String, String) {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+
+        stream
+            .write_all(
+                b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\
+                  x-client-cert-subject: Q049cm9vdA==\r\nx-client-cert-serial: 01\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let response = String::from_utf8(response).unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let (subject, serial) = body.split_once('\n').unwrap();
+
+        (subject.to_string(), serial.to_string())
+    }
+
+    fn client_config(
+        ca: &Certificate,
+    ) -> ::rustls::ConfigBuilder<ClientConfig, ::rustls::client::WantsClientCert> {
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.der().clone()).unwrap();
+
+        ClientConfig::builder().with_root_certificates(roots)
+    }
+
+    #[tokio::test]
+    async fn forwards_non_ascii_subjects_of_client_certificates() {
+        let (addr, issuer, ca) = serve().await;
+
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Иван Петров");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Рога и копыта");
+        let (cert, key) = signed(params, &issuer);
+        let config = client_config(&ca)
+            .with_client_auth_cert(
+                vec![cert.der().clone()],
+                PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+            )
+            .unwrap();
+
+        let (subject, serial) = request(addr, config).await;
+
+        assert_eq!(STANDARD.decode(subject).unwrap(), SUBJECT.as_bytes());
+        assert!(!serial.is_empty() && serial != "01");
+    }
+
+    #[tokio::test]
+    async fn drops_certificate_headers_of_clients_without_certificate() {
+        let (addr, _, ca) = serve().await;
+
+        let config = client_config(&ca).with_no_client_auth();
+
+        assert_eq!(request(addr, config).await, (String::new(), String::new()));
+    }
+}
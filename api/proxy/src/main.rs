@@ -1,6 +1,6 @@
 mod app;
 
-use crate::app::init_app;
+use crate::app::{ClientCertAcceptor, init_app, rustls_config};
 use ::api_util::Error;
 use ::api_util::{
     console::*,
@@ -10,6 +10,7 @@ use ::api_util::{
     prometheus,
     shutdown::create_shutdown_handle,
 };
+use ::axum_server::tls_rustls::RustlsAcceptor;
 use ::std::net::SocketAddr;
 
 #[tokio::main]
@@ -22,22 +23,26 @@ async fn main() -> Result<(), Box<Error>> {
 
     print_service_started(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
-    use axum_server::tls_rustls::RustlsConfig;
-    let config = RustlsConfig::from_pem_file(
+    let config = rustls_config(
         env::get_var_or_default("SSL_CRT", "/etc/ssl/private/cert.pem"),
         env::get_var_or_default("SSL_KEY", "/etc/ssl/private/key.pem"),
+        env::get_var("SSL_CLIENT_CA")
+            .filter(|path| !path.is_empty())
+            .as_deref(),
     )
     .await
     .unwrap();
+    let acceptor = ClientCertAcceptor::new(RustlsAcceptor::new(config));
     let addr = SocketAddr::from(([0, 0, 0, 0], 443));
 
     tokio::spawn(prometheus::start_metrics_server(Some(
         shutdown_handle.clone(),
     )));
 
-    if let Err(err) = axum_server::bind_rustls(addr, config)
+    if let Err(err) = axum_server::bind(addr)
+        .acceptor(acceptor)
         .handle(shutdown_handle)
-        .serve(init_app().into_make_service())
+        .serve(init_app().into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
         error!("failed to start HTTP server: {}", err);
@@ -1,9 +1,12 @@
-use ::api_util::amqp::{DeliveryExt, DeliveryResult};
+use ::api_util::{
+    amqp::{DeliveryExt, DeliveryResult},
+    log,
+};
 
 pub async fn consumer(delivery: DeliveryResult) {
     let Ok(Some(delivery)) = delivery else { return };
-    
-    // TODO: setup broadcast consumer delegate
+
+    log::handle_log_control(env!("CARGO_PKG_NAME"), &delivery);
 
     delivery.confirm()
-}
\ No newline at end of file
+}
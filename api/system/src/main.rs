@@ -21,6 +21,6 @@ async fn main() -> Result<(), Box<Error>> {
 
     wait_for_shutdown_signals().await;
 
-    print_service_stopped();
+    print_service_stopped().await;
     Ok(())
 }
@@ -1,17 +1,33 @@
-use ::deadpool_lapin::lapin::{message::Delivery, options::BasicAckOptions};
-use ::serde::de::DeserializeOwned;
+use crate::{
+    entity_event::{ENTITY_EVENT_VERSION, EntityAction, EntityEvent},
+    trace::{REQUEST_ID_HEADER, TRACEPARENT_HEADER, TraceContext},
+};
+use ::deadpool_lapin::lapin::{
+    message::Delivery,
+    options::{BasicAckOptions, BasicNackOptions},
+    types::AMQPValue,
+};
+use ::serde::de::{DeserializeOwned, Error as _};
 use ::serde_json::Error;
-use ::std::borrow::Cow;
+use ::std::{borrow::Cow, time::Duration};
 use ::tracing::error;
 
 pub trait DeliveryExt {
     fn app_id(&self) -> &str;
     fn message_id(&self) -> &str;
     fn reply_to(&self) -> &str;
+    fn correlation_id(&self) -> &str;
+    fn message_type(&self) -> &str;
+    fn timestamp(&self) -> Option<u64>;
+    fn header(&self, name: &str) -> Option<&str>;
+    fn trace_context(&self) -> TraceContext;
     fn confirm(self);
+    fn requeue(self);
+    fn requeue_after(self, delay: Duration);
     fn extract_string(&self) -> String;
-    fn extract_str(&self) -> Cow<str>;
+    fn extract_str(&self) -> Cow<'_, str>;
     fn extract_json<T: DeserializeOwned>(&self) -> Result<T, Error>;
+    fn extract_entity_event<T: DeserializeOwned>(&self) -> Result<EntityEvent<T>, Error>;
 }
 
 impl DeliveryExt for Delivery {
@@ -33,23 +49,104 @@ impl DeliveryExt for Delivery {
             .map_or("", |s| s.as_str())
     }
 
+    fn correlation_id(&self) -> &str {
+        self.properties
+            .correlation_id()
+            .as_ref()
+            .map_or("", |s| s.as_str())
+    }
+
+    fn message_type(&self) -> &str {
+        self.properties.kind().as_ref().map_or("", |s| s.as_str())
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        *self.properties.timestamp()
+    }
+
+    /// String value of a message header, other value types are ignored
+    fn header(&self, name: &str) -> Option<&str> {
+        let (_, value) = self
+            .properties
+            .headers()
+            .as_ref()?
+            .inner()
+            .iter()
+            .find(|(key, _)| key.as_str() == name)?;
+
+        match value {
+            AMQPValue::LongString(value) => std::str::from_utf8(value.as_bytes()).ok(),
+            AMQPValue::ShortString(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Continues the trace of the publisher, the correlation id stands for the request id
+    /// of messages published without one
+    fn trace_context(&self) -> TraceContext {
+        let request_id = self
+            .header(REQUEST_ID_HEADER)
+            .or_else(|| Some(self.correlation_id()).filter(|id| !id.is_empty()));
+
+        TraceContext::continue_from(self.header(TRACEPARENT_HEADER), request_id)
+    }
+
     fn confirm(self) {
         tokio::spawn(async move {
             handle_delivery_ack(self).await;
         });
     }
 
+    fn requeue(self) {
+        tokio::spawn(async move {
+            handle_delivery_nack(self).await;
+        });
+    }
+
+    /// Requeues the delivery once `delay` passed, so a consumer failing on every
+    /// delivery does not spin on the same message. It counts against the prefetch
+    /// until then.
+    fn requeue_after(self, delay: Duration) {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            handle_delivery_nack(self).await;
+        });
+    }
+
     fn extract_string(&self) -> String {
         String::from_utf8_lossy(&self.data).to_string()
     }
 
-    fn extract_str(&self) -> Cow<str> {
+    fn extract_str(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(&self.data)
     }
 
     fn extract_json<T: DeserializeOwned>(&self) -> Result<T, Error> {
         serde_json::from_slice(&self.data)
     }
+
+    /// Decodes an [`EntityEvent`], rejecting envelope versions newer than this build and
+    /// events whose action differs from the routing key they were published under
+    fn extract_entity_event<T: DeserializeOwned>(&self) -> Result<EntityEvent<T>, Error> {
+        let event = self.extract_json::<EntityEvent<T>>()?;
+
+        if event.version > ENTITY_EVENT_VERSION {
+            return Err(Error::custom(format!(
+                "unsupported entity event version {}",
+                event.version
+            )));
+        }
+
+        if EntityAction::from_routing_key(self.routing_key.as_str()) != Some(event.action) {
+            return Err(Error::custom(format!(
+                "entity event action '{}' does not match routing key '{}'",
+                event.action.as_str(),
+                self.routing_key.as_str()
+            )));
+        }
+
+        Ok(event)
+    }
 }
 
 async fn handle_delivery_ack(delivery: Delivery) {
@@ -61,3 +158,18 @@ async fn handle_delivery_ack(delivery: Delivery) {
         );
     }
 }
+
+async fn handle_delivery_nack(delivery: Delivery) {
+    let options = BasicNackOptions {
+        requeue: true,
+        ..Default::default()
+    };
+
+    if let Err(err) = delivery.nack(options).await {
+        error!(
+            error = %err,
+            delivery_tag = delivery.delivery_tag,
+            "failed to requeue AMQP delivery"
+        );
+    }
+}
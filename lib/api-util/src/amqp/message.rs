@@ -1,4 +1,5 @@
-use super::{BasicProperties, FieldTable, ShortString};
+use super::{AMQPValue, BasicProperties, FieldTable, LongString, ShortString};
+use crate::trace::{REQUEST_ID_HEADER, TRACEPARENT_HEADER, TraceContext};
 
 #[derive(Default)]
 pub struct AMQPMessageOptions {
@@ -85,4 +86,23 @@ impl AMQPMessageOptions {
     pub fn with_cluster_id(self, value: impl AsRef<str>) -> Self {
         self.with_string_property(value, |props, val| props.with_cluster_id(val))
     }
+
+    /// Adds the `traceparent` and `x-request-id` headers of `context`, its request id is
+    /// also the correlation id unless one is set
+    pub fn with_trace_context(mut self, context: &TraceContext) -> Self {
+        let mut headers = self.properties.headers().clone().unwrap_or_default();
+        headers.insert(
+            ShortString::from(TRACEPARENT_HEADER),
+            AMQPValue::LongString(LongString::from(context.traceparent())),
+        );
+        headers.insert(
+            ShortString::from(REQUEST_ID_HEADER),
+            AMQPValue::LongString(LongString::from(context.request_id.as_str())),
+        );
+
+        if self.properties.correlation_id().is_none() {
+            self = self.with_correlation_id(&context.request_id);
+        }
+        self.with_headers(headers)
+    }
 }
@@ -1,18 +1,23 @@
 use super::*;
-use crate::Error;
+use crate::{Error, trace::TraceContext};
 use ::deadpool_lapin::{
     Config, Pool, Runtime,
-    lapin::{Channel, Queue},
+    lapin::{Channel, Queue, publisher_confirm::Confirmation},
 };
+use ::std::{future::Future, pin::Pin};
+use ::tokio::sync::Mutex;
+use ::tracing::{Instrument, info_span};
 pub use deadpool_lapin::lapin::{
     BasicProperties, ConsumerDelegate, ExchangeKind, message::*, options::*, types::*,
 };
 
 const DEFAULT_CONSUMER_TAG_SUFFIX: &str = "consumer";
 
+/// Connections to the broker. Messages are published on a shared channel in confirm mode,
+/// opened on first use and replaced by a fresh one from the pool once the broker closes it.
 pub struct AMQPPool {
     pool: Pool,
-    channel: Channel,
+    channel: Mutex<Option<Channel>>,
 }
 
 impl AMQPPool {
@@ -24,8 +29,10 @@ impl AMQPPool {
         let pool = config
             .create_pool(Some(Runtime::Tokio1))
             .map_err(map_amqp_err)?;
-        let channel = create_channel(&pool).await?;
-        Ok(Self { pool, channel })
+        Ok(Self {
+            pool,
+            channel: Mutex::new(None),
+        })
     }
 
     pub async fn set_delegate<D: ConsumerDelegate + Clone + 'static>(
@@ -98,29 +105,105 @@ impl AMQPPool {
             .basic_consume(name, &consumer_tag, consume_options, FieldTable::default())
             .await
             .map_err(map_amqp_err)?
-            .set_delegate(delegate.clone());
+            .set_delegate(TracedDelegate(delegate.clone()));
         Ok(())
     }
 
+    /// Publishes `payload` and waits for the broker to confirm it, the trace context of the
+    /// operation being served travels along in the message headers. A message the broker
+    /// refuses fails, one sent on a closed channel is retried once on a fresh channel.
     pub async fn send(
         &self,
         exchange: ExchangeKind,
         routing_key: &str,
-        options: AMQPMessageOptions,
+        mut options: AMQPMessageOptions,
         payload: &[u8],
     ) -> Result<(), Error> {
-        let publish_options = OptionsBuilder::publish_options(&options);
-        self.channel
-            .basic_publish(
-                exchange_kind_to_str(&exchange),
-                routing_key,
-                publish_options,
-                payload,
-                options.properties,
-            )
-            .await
-            .map_err(map_amqp_err)?;
-        Ok(())
+        if let Some(context) = TraceContext::current() {
+            options = options.with_trace_context(&context);
+        }
+
+        let channel = self.publish_channel().await?;
+        match publish(&channel, &exchange, routing_key, &options, payload).await {
+            Err(_) if !channel.status().connected() => {
+                let channel = self.publish_channel().await?;
+                publish(&channel, &exchange, routing_key, &options, payload).await
+            }
+            result => result,
+        }
+    }
+
+    /// Channel messages are published on, opened unless the current one is still usable
+    async fn publish_channel(&self) -> Result<Channel, Error> {
+        let mut channel = self.channel.lock().await;
+        match channel.as_ref() {
+            Some(channel) if channel.status().connected() => Ok(channel.clone()),
+            _ => {
+                let opened = create_publish_channel(&self.pool).await?;
+                *channel = Some(opened.clone());
+                Ok(opened)
+            }
+        }
+    }
+}
+
+async fn publish(
+    channel: &Channel,
+    exchange: &ExchangeKind,
+    routing_key: &str,
+    options: &AMQPMessageOptions,
+    payload: &[u8],
+) -> Result<(), Error> {
+    let confirmation = channel
+        .basic_publish(
+            exchange_kind_to_str(exchange),
+            routing_key,
+            OptionsBuilder::publish_options(options),
+            payload,
+            options.properties.clone(),
+        )
+        .await
+        .map_err(map_amqp_err)?
+        .await
+        .map_err(map_amqp_err)?;
+
+    match confirmation {
+        Confirmation::Nack(_) => Err(Error::Amqp(format!(
+            "'{routing_key}' message not confirmed by the broker"
+        ))),
+        Confirmation::Ack(_) | Confirmation::NotRequested => Ok(()),
+    }
+}
+
+/// Serves every delivery inside a span restoring the trace context of its publisher
+#[derive(Clone)]
+struct TracedDelegate<D>(D);
+
+impl<D: ConsumerDelegate> ConsumerDelegate for TracedDelegate<D> {
+    fn on_new_delivery(
+        &self,
+        delivery: DeliveryResult,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let Ok(Some(message)) = &delivery else {
+            return self.0.on_new_delivery(delivery);
+        };
+
+        let context = message.trace_context();
+        let span = info_span!(
+            "delivery",
+            routing_key = message.routing_key.as_str(),
+            app_id = message.app_id(),
+            trace_id = %context.trace_id,
+            span_id = %context.span_id,
+            parent_id = context.parent_id.as_deref(),
+            request_id = %context.request_id,
+        );
+
+        Box::pin(
+            context
+                .scope(self.0.on_new_delivery(delivery))
+                .instrument(span),
+        )
     }
 }
 
@@ -180,6 +263,15 @@ async fn create_channel(pool: &Pool) -> Result<Channel, Error> {
     Ok(channel)
 }
 
+async fn create_publish_channel(pool: &Pool) -> Result<Channel, Error> {
+    let channel = create_channel(pool).await?;
+    channel
+        .confirm_select(ConfirmSelectOptions::default())
+        .await
+        .map_err(map_amqp_err)?;
+    Ok(channel)
+}
+
 fn exchange_kind_to_str(kind: &ExchangeKind) -> &str {
     match kind {
         ExchangeKind::Custom(name) => name,
@@ -2,8 +2,12 @@ use crate::amqp::AMQPMessageOptions;
 use crate::{
     Error,
     amqp::{AMQPPool, ExchangeKind},
+    auth_event::AuthAction,
+    entity_event::EntityEvent,
+    log_control::{LOG_LEVEL_MESSAGE_TYPE, LogLevelControl},
 };
 use ::serde::Serialize;
+use ::uuid::Uuid;
 
 pub trait AMQPPoolExt {
     fn send_message(
@@ -18,6 +22,17 @@ pub trait AMQPPoolExt {
         options: AMQPMessageOptions,
         payload: &S,
     ) -> impl Future<Output = Result<(), Error>>;
+    fn send_entity_event<T: Serialize>(
+        &self,
+        options: AMQPMessageOptions,
+        event: &EntityEvent<T>,
+    ) -> impl Future<Output = Result<(), Error>>;
+    fn send_auth_event<S: Serialize>(
+        &self,
+        options: AMQPMessageOptions,
+        action: AuthAction,
+        event: &S,
+    ) -> impl Future<Output = Result<(), Error>>;
     fn broadcast_message(
         &self,
         options: AMQPMessageOptions,
@@ -28,6 +43,11 @@ pub trait AMQPPoolExt {
         options: AMQPMessageOptions,
         payload: &S,
     ) -> impl Future<Output = Result<(), Error>>;
+    fn broadcast_log_level(
+        &self,
+        options: AMQPMessageOptions,
+        control: &LogLevelControl,
+    ) -> impl Future<Output = Result<(), Error>>;
 }
 
 impl AMQPPoolExt for AMQPPool {
@@ -56,6 +76,53 @@ impl AMQPPoolExt for AMQPPool {
         .await
     }
 
+    /// Publishes the event under `entity.<action>` as a persistent message. A message id
+    /// is generated unless one is set, it is what consumers deduplicate deliveries by.
+    async fn send_entity_event<T: Serialize>(
+        &self,
+        mut options: AMQPMessageOptions,
+        event: &EntityEvent<T>,
+    ) -> Result<(), Error> {
+        if options.properties.message_id().is_none() {
+            options = options.with_message_id(Uuid::new_v4().to_string());
+        }
+        if let Some(correlation_id) = &event.correlation_id {
+            options = options.with_correlation_id(correlation_id);
+        }
+
+        self.send_json(
+            event.action.routing_key(),
+            options
+                .with_content_type("application/json")
+                .with_delivery_mode(2)
+                .with_timestamp(event.occurred_at.max(0) as u64),
+            event,
+        )
+        .await
+    }
+
+    /// Publishes the event under `auth.*` as a persistent message with a generated
+    /// message id unless one is set
+    async fn send_auth_event<S: Serialize>(
+        &self,
+        mut options: AMQPMessageOptions,
+        action: AuthAction,
+        event: &S,
+    ) -> Result<(), Error> {
+        if options.properties.message_id().is_none() {
+            options = options.with_message_id(Uuid::new_v4().to_string());
+        }
+
+        self.send_json(
+            action.routing_key(),
+            options
+                .with_content_type("application/json")
+                .with_delivery_mode(2),
+            event,
+        )
+        .await
+    }
+
     async fn broadcast_message(
         &self,
         options: AMQPMessageOptions,
@@ -77,6 +144,20 @@ impl AMQPPoolExt for AMQPPool {
         )
         .await
     }
+
+    async fn broadcast_log_level(
+        &self,
+        options: AMQPMessageOptions,
+        control: &LogLevelControl,
+    ) -> Result<(), Error> {
+        self.broadcast_json(
+            options
+                .with_type(LOG_LEVEL_MESSAGE_TYPE)
+                .with_content_type("application/json"),
+            control,
+        )
+        .await
+    }
 }
 
 // impl AMQPPool {
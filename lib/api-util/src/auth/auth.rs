@@ -1,4 +1,4 @@
-use crate::model::Permissions;
+use super::Permissions;
 use ::serde::{Deserialize, Serializer};
 use ::std::borrow::Cow;
 
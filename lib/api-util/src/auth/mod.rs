@@ -0,0 +1,12 @@
+#[allow(clippy::module_inception)]
+mod auth;
+mod capabilities;
+mod claims;
+mod permissions;
+
+pub use self::{
+    auth::*,
+    capabilities::*,
+    claims::*,
+    permissions::*,
+};
\ No newline at end of file
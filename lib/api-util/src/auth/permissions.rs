@@ -1,9 +1,16 @@
 #![allow(dead_code)]
-use super::capabilities::Capabilities;
+use super::Capabilities;
 use ::base64::{Engine, engine::general_purpose::STANDARD};
 use ::bitflags::Flags;
 use ::std::collections::HashMap;
 
+/// Index of the system capabilities in the permissions map
+pub const SYSTEM_PERMISSION: u16 = 0;
+/// Index of the access capabilities in the permissions map
+pub const ACCESS_PERMISSION: u16 = 1;
+/// Index of the audit capabilities in the permissions map
+pub const AUDIT_PERMISSION: u16 = 2;
+
 #[derive(Clone)]
 pub struct Permissions {
     inner: Vec<Capabilities>,
@@ -1,5 +1,4 @@
 use ::axum::http::StatusCode;
-use ::serde_json::Value;
 
 #[allow(dead_code)]
 #[derive(Debug, thiserror::Error)]
@@ -16,19 +15,57 @@ pub enum AuthError {
     InvalidToken,
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("Session binding mismatch")]
+    SessionMismatch,
+    #[error("Active sessions limit exceeded")]
+    SessionLimitExceeded,
+    // Device authorization grant errors keep the RFC 8628 error codes
+    #[error("authorization_pending")]
+    AuthorizationPending,
+    #[error("slow_down")]
+    SlowDown,
+    #[error("expired_token")]
+    ExpiredToken,
+    #[error("access_denied")]
+    AccessDenied,
+    // OAuth 2.0 and OpenID Connect errors keep the RFC 6749 and OIDC Core error codes
+    #[error("invalid_request")]
+    InvalidRequest,
+    #[error("invalid_client")]
+    InvalidClient,
+    #[error("invalid_grant")]
+    InvalidGrant,
+    #[error("invalid_scope")]
+    InvalidScope,
+    #[error("unsupported_grant_type")]
+    UnsupportedGrantType,
+    #[error("unsupported_response_type")]
+    UnsupportedResponseType,
+    #[error("login_required")]
+    LoginRequired,
 }
 
 impl AuthError {
     pub fn status_code(&self) -> StatusCode {
-        let status_code = match self {
+        match self {
             Self::Unauthorized
             | Self::WrongCredentials
             | Self::InvalidToken
-            | Self::MissingToken => StatusCode::UNAUTHORIZED,
+            | Self::MissingToken
+            | Self::SessionMismatch
+            | Self::InvalidClient
+            | Self::LoginRequired => StatusCode::UNAUTHORIZED,
             Self::TokenCreation => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::AccessForbidden => StatusCode::FORBIDDEN,
-        };
-
-        status_code
+            Self::AccessForbidden | Self::SessionLimitExceeded => StatusCode::FORBIDDEN,
+            Self::AuthorizationPending
+            | Self::SlowDown
+            | Self::ExpiredToken
+            | Self::AccessDenied
+            | Self::InvalidRequest
+            | Self::InvalidGrant
+            | Self::InvalidScope
+            | Self::UnsupportedGrantType
+            | Self::UnsupportedResponseType => StatusCode::BAD_REQUEST,
+        }
     }
 }
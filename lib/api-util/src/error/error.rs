@@ -24,8 +24,12 @@ pub enum Error {
     #[error("{0}")]
     Amqp(String),
     #[error[transparent]]
+    LogControl(#[from] super::LogControlError),
+    #[error[transparent]]
     JsonRejection(#[from] JsonRejection),
     #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
     Unknown(&'static str),
 }
 
@@ -43,6 +47,7 @@ impl IntoResponse for Error {
         let (code, message, details) = match self {
             Self::AuthError(err) => (err.status_code(), &*err.to_string(), Value::Null),
             Self::JsonRejection(err) => (StatusCode::BAD_REQUEST, &*err.to_string(), Value::Null),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, &*message.clone(), Value::Null),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Something went wrong",
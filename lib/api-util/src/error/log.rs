@@ -0,0 +1,12 @@
+use ::tracing_subscriber::filter::ParseError;
+
+/// Why the log directives of a service could not be changed
+#[derive(Debug, thiserror::Error)]
+pub enum LogControlError {
+    #[error("invalid log directives: {0}")]
+    InvalidDirectives(#[from] ParseError),
+    #[error("logger is not installed")]
+    NotInstalled,
+    #[error("log filter reload failed")]
+    ReloadFailed,
+}
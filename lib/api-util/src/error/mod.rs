@@ -1,8 +1,11 @@
+#[allow(clippy::module_inception)]
 mod error;
 pub mod panic;
 mod auth;
+mod log;
 
 pub use self::{
     error::Error,
     auth::AuthError,
+    log::LogControlError,
 };
\ No newline at end of file
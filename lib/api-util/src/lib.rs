@@ -1,4 +1,5 @@
 pub mod amqp;
+pub mod auth;
 mod error;
 pub mod handler;
 mod macros;
@@ -0,0 +1,137 @@
+use ::chrono::Utc;
+use ::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthAction {
+    Login,
+    LoginFailed,
+    Refresh,
+    RefreshFailed,
+    Revoke,
+    SessionLimit,
+    PermissionsReload,
+}
+
+impl AuthAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Login => "login",
+            Self::LoginFailed => "login_failed",
+            Self::Refresh => "refresh",
+            Self::RefreshFailed => "refresh_failed",
+            Self::Revoke => "revoke",
+            Self::SessionLimit => "session_limit",
+            Self::PermissionsReload => "permissions_reload",
+        }
+    }
+
+    /// Topic exchange routing key the action is published under
+    pub fn routing_key(&self) -> &'static str {
+        match self {
+            Self::Login => "auth.login",
+            Self::LoginFailed => "auth.login.failed",
+            Self::Refresh => "auth.refresh",
+            Self::RefreshFailed => "auth.refresh.failed",
+            Self::Revoke => "auth.revoke",
+            Self::SessionLimit => "auth.session.limit",
+            Self::PermissionsReload => "auth.permissions.reload",
+        }
+    }
+
+    pub fn from_routing_key(routing_key: &str) -> Option<Self> {
+        match routing_key {
+            "auth.login" => Some(Self::Login),
+            "auth.login.failed" => Some(Self::LoginFailed),
+            "auth.refresh" => Some(Self::Refresh),
+            "auth.refresh.failed" => Some(Self::RefreshFailed),
+            "auth.revoke" => Some(Self::Revoke),
+            "auth.session.limit" => Some(Self::SessionLimit),
+            "auth.permissions.reload" => Some(Self::PermissionsReload),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of an authentication attempt or of a change to what tokens grant.
+///
+/// `login` is set instead of `user_id` when the credentials matched no user.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuthEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(flatten)]
+    pub session_limit: Option<SessionLimit>,
+    pub occurred_at: i64,
+}
+
+/// Active sessions limit a login ran into, sessions evicted to make room for it are
+/// listed by id
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionLimit {
+    pub limit: u32,
+    pub policy: String,
+    pub evicted: Vec<String>,
+}
+
+impl Default for AuthEvent {
+    fn default() -> Self {
+        Self {
+            user_id: None,
+            login: None,
+            method: None,
+            device: None,
+            ip: None,
+            reason: None,
+            session_limit: None,
+            occurred_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+impl AuthEvent {
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn with_login(mut self, login: impl Into<String>) -> Self {
+        self.login = Some(login.into());
+        self
+    }
+
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self
+    }
+
+    pub fn with_device(mut self, device: Option<impl Into<String>>) -> Self {
+        self.device = device.map(Into::into);
+        self
+    }
+
+    pub fn with_ip(mut self, ip: Option<String>) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    pub fn with_session_limit(mut self, session_limit: SessionLimit) -> Self {
+        self.session_limit = Some(session_limit);
+        self
+    }
+}
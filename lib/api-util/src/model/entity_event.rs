@@ -0,0 +1,124 @@
+use ::chrono::Utc;
+use ::serde::{Deserialize, Serialize};
+
+/// Version of the [`EntityEvent`] envelope published by this build
+pub const ENTITY_EVENT_VERSION: u16 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl EntityAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Deleted => "deleted",
+        }
+    }
+
+    /// Topic exchange routing key the action is published under
+    pub fn routing_key(&self) -> &'static str {
+        match self {
+            Self::Created => "entity.created",
+            Self::Updated => "entity.updated",
+            Self::Deleted => "entity.deleted",
+        }
+    }
+
+    pub fn from_routing_key(routing_key: &str) -> Option<Self> {
+        match routing_key {
+            "entity.created" => Some(Self::Created),
+            "entity.updated" => Some(Self::Updated),
+            "entity.deleted" => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EntityRef {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+}
+
+/// Versioned envelope of an entity change, consumed by the audit service.
+///
+/// `before` is expected for updates and deletions, `after` for creations and updates.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EntityEvent<T> {
+    pub version: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    pub entity: EntityRef,
+    pub action: EntityAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<T>,
+    pub occurred_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl<T> EntityEvent<T> {
+    pub fn new(action: EntityAction, kind: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            version: ENTITY_EVENT_VERSION,
+            actor: None,
+            entity: EntityRef {
+                kind: kind.into(),
+                id: id.into(),
+            },
+            action,
+            before: None,
+            after: None,
+            occurred_at: Utc::now().timestamp(),
+            correlation_id: None,
+        }
+    }
+
+    pub fn created(kind: impl Into<String>, id: impl Into<String>, after: T) -> Self {
+        Self::new(EntityAction::Created, kind, id).with_after(after)
+    }
+
+    pub fn updated(kind: impl Into<String>, id: impl Into<String>, before: T, after: T) -> Self {
+        Self::new(EntityAction::Updated, kind, id)
+            .with_before(before)
+            .with_after(after)
+    }
+
+    pub fn deleted(kind: impl Into<String>, id: impl Into<String>, before: T) -> Self {
+        Self::new(EntityAction::Deleted, kind, id).with_before(before)
+    }
+
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn with_before(mut self, before: T) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    pub fn with_after(mut self, after: T) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    pub fn with_occurred_at(mut self, timestamp: i64) -> Self {
+        self.occurred_at = timestamp;
+        self
+    }
+
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+}
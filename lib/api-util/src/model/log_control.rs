@@ -0,0 +1,27 @@
+use ::serde::{Deserialize, Serialize};
+
+/// AMQP message type of a [`LogLevelControl`] on the broadcast exchange
+pub const LOG_LEVEL_MESSAGE_TYPE: &str = "log.level";
+
+/// Changes the log directives of running services without a restart
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct LogLevelControl {
+    /// Service to change, every service when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// `EnvFilter` directives such as `info,access=debug`, the startup directives are
+    /// restored when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directives: Option<String>,
+    /// Seconds after which the startup directives are restored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_after: Option<u64>,
+}
+
+impl LogLevelControl {
+    pub fn applies_to(&self, service: &str) -> bool {
+        self.service
+            .as_deref()
+            .is_none_or(|target| target == service)
+    }
+}
@@ -0,0 +1,31 @@
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::{Map, Value};
+
+/// Span entered when an event was recorded, outermost first in `LogRecord::spans`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogSpan {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// One tracing event as shipped to the logger service and stored as a JSON line.
+///
+/// `trace_id` is the `trace_id` field of the event or of its innermost span recording one.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogRecord {
+    /// RFC 3339 UTC timestamp with microseconds
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<LogSpan>,
+    pub service: String,
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
@@ -1 +1,5 @@
+pub mod auth_event;
+pub mod entity_event;
+pub mod log_control;
+pub mod log_record;
 pub mod metadata;
\ No newline at end of file
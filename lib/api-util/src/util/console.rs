@@ -1,3 +1,4 @@
+use super::log::flush_logs;
 use ::tracing::info;
 
 const SERVICE_STARTED_MSG: &str = "service started";
@@ -14,6 +15,8 @@ pub fn print_service_started(package: &str, version: &str) {
     info!("{WAITING_FOR_SHUTDOWN_MSG}");
 }
 
-pub fn print_service_stopped() {
+/// Also waits for the queued log records to be shipped, so the last lines are kept
+pub async fn print_service_stopped() {
     info!("{SERVICE_STOPPED_MSG}");
+    flush_logs().await;
 }
\ No newline at end of file
@@ -1,46 +1,138 @@
-use crate::amqp::{AMQPMessageOptions, AMQPPool, ExchangeKind};
-use ::std::{io, sync::Mutex};
+mod redact;
+mod spool;
+
+use self::spool::Spool;
+use crate::{
+    Error, LogControlError,
+    amqp::{AMQPMessageOptions, AMQPPool, Delivery, DeliveryExt, ExchangeKind},
+    env,
+    log_control::{LOG_LEVEL_MESSAGE_TYPE, LogLevelControl},
+    log_record::{LogRecord, LogSpan},
+};
+use ::chrono::{SecondsFormat, Utc};
+use ::serde_json::{Map, Value};
+use ::std::{
+    fmt::Debug,
+    io::{self, Write},
+    mem,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+use ::tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot,
+    },
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval, sleep, timeout},
+};
+use ::tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
 use ::tracing_appender::{
     non_blocking::WorkerGuard,
-    rolling::{RollingFileAppender, Rotation},
+    rolling::{InitError, RollingFileAppender, Rotation},
 };
 use ::tracing_subscriber::{
-    EnvFilter, filter::LevelFilter, fmt::layer, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Registry,
+    filter::LevelFilter,
+    fmt::{MakeWriter, layer},
+    layer::{Context, Layer, SubscriberExt},
+    registry::LookupSpan,
+    reload,
+    util::SubscriberInitExt,
 };
 
+pub use self::redact::{REDACTED, redact, redact_record};
 pub use ::tracing::{debug, error, info, trace, warn};
 pub use ::tracing_appender::rolling as rolling_appender;
 
+const LOG_ROUTING_KEY: &str = "log.write";
+const LOG_CONTENT_TYPE: &str = "application/x-ndjson";
+const LOG_DROPPED_METRIC_NAME: &str = "log_records_dropped_total";
+const LOG_SHIPPED_METRIC_NAME: &str = "log_records_shipped_total";
+
+/// Sender of the background log shipper and its flush timeout, set once the AMQP logger
+/// is installed
+static SHIPPER: OnceLock<(mpsc::Sender<ShipperMessage>, Duration)> = OnceLock::new();
+/// Filter of the installed logger, swapped by [`set_log_directives`]
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+/// Pending restoration of the startup directives
+static FILTER_REVERT: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+enum ShipperMessage {
+    Record(Vec<u8>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Limits of the AMQP log shipper. Records are published in JSON Lines batches once a
+/// batch reaches `batch_size` bytes or `batch_interval` passes, and records arriving
+/// while `capacity` records wait for the shipper are dropped. Batches the broker refuses
+/// are spooled under `spool_path`, up to `spool_size` bytes per service. [`flush_logs`]
+/// gives up on the shipper after `flush_timeout`.
+pub struct ShipperConfig {
+    pub capacity: usize,
+    pub batch_size: usize,
+    pub batch_interval: Duration,
+    pub spool_path: PathBuf,
+    pub spool_size: u64,
+    pub flush_timeout: Duration,
+}
+
+impl ShipperConfig {
+    pub fn from_env() -> Self {
+        Self {
+            capacity: env::get_var_or_default("LOG_QUEUE_CAPACITY", "10000")
+                .parse()
+                .unwrap_or(10_000),
+            batch_size: env::get_var_or_default("LOG_BATCH_SIZE", "65536")
+                .parse()
+                .unwrap_or(65_536),
+            batch_interval: Duration::from_millis(
+                env::get_var_or_default("LOG_BATCH_INTERVAL_MS", "1000")
+                    .parse()
+                    .unwrap_or(1000),
+            ),
+            spool_path: env::get_var("LOG_SPOOL_PATH").map_or_else(
+                || PathBuf::from(env::get_var_or_default("DATA_PATH", "/etc/u2")).join("log-spool"),
+                PathBuf::from,
+            ),
+            spool_size: env::get_var_or_default("LOG_SPOOL_SIZE", "67108864")
+                .parse()
+                .unwrap_or(67_108_864),
+            flush_timeout: Duration::from_millis(
+                env::get_var_or_default("LOG_FLUSH_TIMEOUT_MS", "5000")
+                    .parse()
+                    .unwrap_or(5000),
+            ),
+        }
+    }
+}
+
+/// Hands serialized records over to the background shipper without blocking, so logging
+/// never waits for the broker and works outside of a runtime
 pub struct LoggerWriter {
-    app: &'static str,
-    amqp: &'static AMQPPool,
+    sender: mpsc::Sender<ShipperMessage>,
 }
 
 impl LoggerWriter {
-    pub fn new(app: &'static str, pool: &'static AMQPPool) -> Self {
-        Self { app, amqp: pool }
+    fn new(sender: mpsc::Sender<ShipperMessage>) -> Self {
+        Self { sender }
     }
 }
 
 impl io::Write for LoggerWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let app = self.app;
-        let pool = self.amqp;
-        let buf_owned = buf.to_owned();
-
-        tokio::spawn(async move {
-            if let Err(err) = pool
-                .send(
-                    ExchangeKind::Topic,
-                    "log.write",
-                    AMQPMessageOptions::default().with_app_id(app),
-                    &buf_owned,
-                )
-                .await
-            {
-                eprintln!("'log.write' sending AMQP message: {err}");
+        if let Err(err) = self.sender.try_send(ShipperMessage::Record(buf.to_owned())) {
+            let reason = match err {
+                TrySendError::Full(_) => "full",
+                TrySendError::Closed(_) => "closed",
             };
-        });
+            metrics::counter!(LOG_DROPPED_METRIC_NAME, "reason" => reason).increment(1);
+        }
 
         Ok(buf.len())
     }
@@ -48,65 +140,563 @@ impl io::Write for LoggerWriter {
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
+}
+
+struct LogBatch {
+    data: Vec<u8>,
+    records: u64,
+}
+
+impl LogBatch {
+    fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            records: 0,
+        }
+    }
 
-    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.write(buf).ok();
-        Ok(())
+    fn push(&mut self, record: Vec<u8>) {
+        self.data.extend_from_slice(&record);
+        self.records += 1;
+    }
+}
+
+/// Destination of the shipped batches
+trait LogPublisher {
+    async fn publish(&self, app: &'static str, data: &[u8]) -> Result<(), Error>;
+}
+
+impl LogPublisher for AMQPPool {
+    async fn publish(&self, app: &'static str, data: &[u8]) -> Result<(), Error> {
+        self.send(
+            ExchangeKind::Topic,
+            LOG_ROUTING_KEY,
+            AMQPMessageOptions::default()
+                .with_app_id(app)
+                .with_content_type(LOG_CONTENT_TYPE),
+            data,
+        )
+        .await
+    }
+}
+
+/// Publishes batches to the logger service, spooling them to disk while the broker is
+/// unreachable or refuses them
+struct Shipper<P: 'static> {
+    app: &'static str,
+    publisher: &'static P,
+    batch: LogBatch,
+    spool: Spool,
+}
+
+impl<P: LogPublisher> Shipper<P> {
+    /// Publishes the pending batch and empties it. Spooled batches go out first so that
+    /// records keep their order, the batch joins them when the broker is still down.
+    async fn ship(&mut self) {
+        let replayed = self.replay().await;
+
+        if self.batch.records == 0 {
+            self.spool.update_metrics();
+            return;
+        }
+
+        let records = self.batch.records;
+        let data = std::mem::take(&mut self.batch.data);
+        self.batch.records = 0;
+
+        if replayed && self.publish(&data, records).await {
+            return;
+        }
+
+        self.spool.push(&data, records).await;
+    }
+
+    /// Publishes the spooled batches oldest first, returns whether the spool was emptied
+    async fn replay(&mut self) -> bool {
+        while let Some((data, records)) = self.spool.front().await {
+            if !self.publish(&data, records).await {
+                return false;
+            }
+            self.spool.pop_front().await;
+        }
+
+        self.spool.is_empty()
+    }
+
+    /// Failures are reported on stderr only, logging about them would feed the shipper
+    async fn publish(&self, data: &[u8], records: u64) -> bool {
+        match self.publisher.publish(self.app, data).await {
+            Ok(()) => {
+                metrics::counter!(LOG_SHIPPED_METRIC_NAME).increment(records);
+                true
+            }
+            Err(err) => {
+                eprintln!("'{LOG_ROUTING_KEY}' sending AMQP message: {err}");
+                false
+            }
+        }
+    }
+}
+
+async fn ship_logs(
+    app: &'static str,
+    pool: &'static AMQPPool,
+    config: ShipperConfig,
+    mut receiver: mpsc::Receiver<ShipperMessage>,
+) {
+    let mut shipper = Shipper {
+        app,
+        publisher: pool,
+        batch: LogBatch::new(),
+        spool: Spool::open(config.spool_path.join(app), config.spool_size).await,
+    };
+    let mut ticker = interval(config.batch_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(ShipperMessage::Record(record)) => {
+                    shipper.batch.push(record);
+                    if shipper.batch.data.len() >= config.batch_size {
+                        shipper.ship().await;
+                    }
+                }
+                Some(ShipperMessage::Flush(done)) => {
+                    shipper.ship().await;
+                    let _ = done.send(());
+                }
+                None => {
+                    shipper.ship().await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => shipper.ship().await,
+        }
+    }
+}
+
+/// Publishes every record queued so far, waits for the shipper to get them out unless
+/// the flush timeout of the [`ShipperConfig`] passes first
+pub async fn flush_logs() {
+    let Some((sender, flush_timeout)) = SHIPPER.get() else {
+        return;
+    };
+
+    let flushed = async {
+        let (done, flushed) = oneshot::channel();
+        if sender.send(ShipperMessage::Flush(done)).await.is_ok() {
+            let _ = flushed.await;
+        }
+    };
+    if timeout(*flush_timeout, flushed).await.is_err() {
+        eprintln!("log records not flushed within {flush_timeout:?}");
+    }
+}
+
+/// Field holding the trace id of an event, recorded on the event itself or on a span
+pub const TRACE_ID_FIELD: &str = "trace_id";
+
+/// Fields recorded on a span, kept in its extensions until the span closes
+struct SpanFields(Map<String, Value>);
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), Value::from(format!("{value:?}")));
     }
 }
 
+/// Serializes every event into a `LogRecord` written as a single JSON line
+pub struct JsonLayer<W> {
+    service: &'static str,
+    host: String,
+    make_writer: W,
+}
+
+impl<W> JsonLayer<W> {
+    pub fn new(service: &'static str, make_writer: W) -> Self {
+        Self {
+            service,
+            host: host_name(),
+            make_writer,
+        }
+    }
+}
+
+impl<S, W> Layer<S> for JsonLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+
+        if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+            values.record(&mut JsonVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        let message = match fields.remove("message") {
+            Some(Value::String(message)) => message,
+            Some(message) => message.to_string(),
+            None => String::new(),
+        };
+
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| LogSpan {
+                        name: span.name().to_string(),
+                        fields: span
+                            .extensions()
+                            .get::<SpanFields>()
+                            .map(|SpanFields(fields)| fields.clone())
+                            .unwrap_or_default(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let trace_id = fields
+            .get(TRACE_ID_FIELD)
+            .or_else(|| {
+                spans
+                    .iter()
+                    .rev()
+                    .find_map(|span| span.fields.get(TRACE_ID_FIELD))
+            })
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned);
+
+        let mut record = LogRecord {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            level: metadata.level().as_str().to_string(),
+            target: metadata.target().to_string(),
+            message,
+            fields,
+            spans,
+            service: self.service.to_string(),
+            host: self.host.clone(),
+            trace_id,
+        };
+        redact_record(&mut record);
+
+        let Ok(mut line) = serde_json::to_vec(&record) else {
+            return;
+        };
+        line.push(b'\n');
+
+        // A record is handed over in one write, so that it is never split between lines
+        self.make_writer
+            .make_writer_for(metadata)
+            .write_all(&line)
+            .ok();
+    }
+}
+
+/// Name of the host recorded in every `LogRecord`
+pub fn host_name() -> String {
+    env::get_var("HOST_NAME")
+        .or_else(|| env::get_var("HOSTNAME"))
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
 fn create_env_filter() -> EnvFilter {
     EnvFilter::builder()
         .with_default_directive(LevelFilter::INFO.into())
         .from_env_lossy()
 }
 
-fn create_stdout_layer() -> impl tracing_subscriber::Layer<tracing_subscriber::Registry> {
-    layer().compact()
+/// Reloadable filter of every logger setup, installed first so that its handle has the
+/// same type whatever layers follow
+fn create_filter_layer() -> reload::Layer<EnvFilter, Registry> {
+    let (filter_layer, handle) = reload::Layer::new(create_env_filter());
+    let _ = FILTER.set(handle);
+    filter_layer
 }
 
-pub fn stdout_logger() {
-    let env_filter = create_env_filter();
-    let stdout_layer = create_stdout_layer();
+fn create_stdout_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    stdout_layer(io::stdout)
+}
+
+/// Formats events as they are printed on stdout into `make_writer`, with secrets scrubbed
+pub fn stdout_layer<S, W>(make_writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    layer().compact().with_writer(RedactedWriter(make_writer))
+}
+
+/// Writer receiving events already formatted, secrets are scrubbed from the text
+struct RedactedWriter<W>(W);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactedWriter<M> {
+    type Writer = RedactedWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedWriter(self.0.make_writer())
+    }
+}
+
+impl<W: Write> Write for RedactedWriter<W> {
+    /// The formatter hands one whole event per call
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
 
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Replaces the log directives, `None` restores the ones the service was started with
+/// from `RUST_LOG`. With `revert_after` the startup directives are restored once it
+/// elapsed, a later call cancels a pending restoration.
+pub fn set_log_directives(
+    directives: Option<&str>,
+    revert_after: Option<Duration>,
+) -> Result<(), LogControlError> {
+    let filter = match directives {
+        Some(directives) => EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .parse(directives)?,
+        None => create_env_filter(),
+    };
+
+    let handle = FILTER.get().ok_or(LogControlError::NotInstalled)?;
+    handle
+        .reload(filter)
+        .map_err(|_| LogControlError::ReloadFailed)?;
+
+    let revert = revert_after.map(|revert_after| {
+        tokio::spawn(async move {
+            sleep(revert_after).await;
+            if FILTER
+                .get()
+                .is_some_and(|handle| handle.reload(create_env_filter()).is_ok())
+            {
+                info!("startup log directives restored");
+            }
+        })
+    });
+    if let Some(pending) = mem::replace(&mut *FILTER_REVERT.lock().unwrap(), revert) {
+        pending.abort();
+    }
+
+    Ok(())
+}
+
+/// Applies a control message received on a broadcast queue, returns whether it was meant
+/// for `service`
+pub fn apply_log_control(
+    service: &str,
+    control: &LogLevelControl,
+) -> Result<bool, LogControlError> {
+    if !control.applies_to(service) {
+        return Ok(false);
+    }
+
+    set_log_directives(
+        control.directives.as_deref(),
+        control.revert_after.map(Duration::from_secs),
+    )?;
+    info!(
+        directives = control.directives.as_deref().unwrap_or("startup"),
+        revert_after = control.revert_after,
+        "log directives changed"
+    );
+
+    Ok(true)
+}
+
+/// Applies the `log.level` control messages of a broadcast delivery addressed to
+/// `service`, other messages are left alone and rejected ones are logged
+pub fn handle_log_control(service: &str, delivery: &Delivery) {
+    if delivery.message_type() != LOG_LEVEL_MESSAGE_TYPE {
+        return;
+    }
+
+    let applied = match delivery.extract_json::<LogLevelControl>() {
+        Ok(control) => apply_log_control(service, &control).map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(err) = applied {
+        warn!("'{LOG_LEVEL_MESSAGE_TYPE}' control message rejected: {err}");
+    }
+}
+
+pub fn stdout_logger() {
     tracing_subscriber::registry()
-        .with(stdout_layer)
-        .with(env_filter)
+        .with(create_filter_layer())
+        .with(create_stdout_layer())
         .init();
 }
 
+/// Logs to stdout and ships JSON records to the logger service through one background
+/// task, see [`ShipperConfig`] for its limits
 pub async fn amqp_logger(app: &'static str, pool: &'static AMQPPool) {
-    let env_filter = create_env_filter();
-    let stdout_layer = create_stdout_layer();
-    let amqp_layer = layer()
-        .compact()
-        .with_ansi(false)
-        .with_writer(Mutex::new(LoggerWriter::new(app, pool)));
+    let config = ShipperConfig::from_env();
+    let (sender, receiver) = mpsc::channel(config.capacity);
+    let _ = SHIPPER.set((sender.clone(), config.flush_timeout));
+    tokio::spawn(ship_logs(app, pool, config, receiver));
+
+    let amqp_layer = JsonLayer::new(app, Mutex::new(LoggerWriter::new(sender)));
 
     tracing_subscriber::registry()
-        .with(stdout_layer)
+        .with(create_filter_layer())
+        .with(create_stdout_layer())
         .with(amqp_layer)
-        .with(env_filter)
         .init();
 }
 
-pub fn file_logger(path: &str, filename: &str) -> WorkerGuard {
-    let env_filter = create_env_filter();
+/// Writes the records of `service` to `<path>/<service>/<host>/<date>.jsonl`, the layout of
+/// the logger service. Nothing is installed when the log directory cannot be set up.
+pub fn file_logger(path: &str, service: &'static str) -> Result<WorkerGuard, InitError> {
     let file_appender = RollingFileAppender::builder()
         .rotation(Rotation::DAILY)
-        .filename_prefix(filename)
-        .filename_suffix("log")
+        .filename_suffix("jsonl")
         .max_log_files(30)
-        .build(path)
-        .expect("failed to initialize rolling file appender");
+        .build(PathBuf::from(path).join(service).join(host_name()))?;
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
-    let stdout_layer = create_stdout_layer();
-    let file_layer = layer().compact().with_ansi(false).with_writer(non_blocking);
+    let file_layer = JsonLayer::new(service, non_blocking);
 
     tracing_subscriber::registry()
-        .with(stdout_layer)
+        .with(create_filter_layer())
+        .with(create_stdout_layer())
         .with(file_layer)
-        .with(env_filter)
         .init();
-    guard
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Broker stand-in refusing every batch while it is down
+    #[derive(Default)]
+    struct FakePublisher {
+        down: AtomicBool,
+        published: Mutex<Vec<String>>,
+    }
+
+    impl LogPublisher for FakePublisher {
+        async fn publish(&self, _app: &'static str, data: &[u8]) -> Result<(), Error> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(Error::Amqp("broker down".to_string()));
+            }
+            self.published
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(data).into_owned());
+            Ok(())
+        }
+    }
+
+    fn spool_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("log-spool-{}-{name}", std::process::id()))
+    }
+
+    async fn shipper(publisher: &'static FakePublisher, dir: PathBuf) -> Shipper<FakePublisher> {
+        Shipper {
+            app: "test",
+            publisher,
+            batch: LogBatch::new(),
+            spool: Spool::open(dir, 1 << 20).await,
+        }
+    }
+
+    async fn ship(shipper: &mut Shipper<FakePublisher>, records: &[&str]) {
+        for record in records {
+            shipper.batch.push(format!("{record}\n").into_bytes());
+        }
+        shipper.ship().await;
+    }
+
+    #[tokio::test]
+    async fn replays_spooled_batches_in_order() {
+        let dir = spool_dir("replay");
+        let publisher = Box::leak(Box::new(FakePublisher::default()));
+        let mut shipper = shipper(publisher, dir.clone()).await;
+
+        publisher.down.store(true, Ordering::SeqCst);
+        ship(&mut shipper, &["1", "2"]).await;
+        ship(&mut shipper, &["3"]).await;
+        assert!(publisher.published.lock().unwrap().is_empty());
+
+        publisher.down.store(false, Ordering::SeqCst);
+        ship(&mut shipper, &["4"]).await;
+
+        assert_eq!(
+            *publisher.published.lock().unwrap(),
+            ["1\n2\n", "3\n", "4\n"]
+        );
+        assert!(shipper.spool.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn replays_batches_spooled_before_a_restart() {
+        let dir = spool_dir("restart");
+        let publisher = Box::leak(Box::new(FakePublisher::default()));
+
+        publisher.down.store(true, Ordering::SeqCst);
+        let mut stopped = shipper(publisher, dir.clone()).await;
+        ship(&mut stopped, &["1"]).await;
+        ship(&mut stopped, &["2"]).await;
+        drop(stopped);
+
+        publisher.down.store(false, Ordering::SeqCst);
+        let mut restarted = shipper(publisher, dir.clone()).await;
+        ship(&mut restarted, &[]).await;
+
+        assert_eq!(*publisher.published.lock().unwrap(), ["1\n", "2\n"]);
+        assert!(restarted.spool.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
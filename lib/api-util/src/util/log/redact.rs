@@ -0,0 +1,279 @@
+use crate::{
+    env,
+    log_record::{LogRecord, LogSpan},
+};
+use ::regex::{Captures, Regex};
+use ::serde_json::{Map, Value};
+use ::std::{borrow::Cow, sync::LazyLock};
+
+/// Replacement of every scrubbed value
+pub const REDACTED: &str = "[REDACTED]";
+
+const DEFAULT_REDACT_FIELDS: &str = "password,password_hash,passwd,secret,client_secret,\
+    totp_secret,token,access_token,refresh_token,id_token,code_verifier,authorization,cookie,\
+    set_cookie,jwt_rt,jwt_secret";
+/// Credentials recognised anywhere in a text, whatever field they are in
+const DEFAULT_REDACT_PATTERNS: [&str; 3] = [
+    // `Authorization` header values
+    r"(?i)\b(?:bearer|basic)\s+[A-Za-z0-9._~+/=-]+",
+    // Refresh token cookie, `JWT_RT=<token>`
+    r"\bJWT_RT=[^;\s,]+",
+    // Compact JWS tokens
+    r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+];
+
+static REDACTOR: LazyLock<Redactor> = LazyLock::new(Redactor::from_env);
+
+/// Scrubs secrets from log output by field name and by pattern.
+///
+/// Field names come from `LOG_REDACT_FIELDS` and are compared case-insensitively, they are
+/// also recognised in rendered text such as `password: "..."`, `password=...` or
+/// `"password":"..."`. Extra patterns are read from `LOG_REDACT_PATTERNS`, whitespace
+/// separated, invalid ones are ignored.
+struct Redactor {
+    fields: Vec<String>,
+    assignment: Option<Regex>,
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    fn from_env() -> Self {
+        let mut fields = env::get_var_or_default("LOG_REDACT_FIELDS", DEFAULT_REDACT_FIELDS)
+            .split(',')
+            .map(|field| field.trim().to_ascii_lowercase())
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<_>>();
+        // Longest first so that `password_hash` is not matched as `password`
+        fields.sort_by_key(|field| std::cmp::Reverse(field.len()));
+
+        let assignment = (!fields.is_empty())
+            .then(|| {
+                let names = fields
+                    .iter()
+                    .map(|field| regex::escape(field))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                // Quotes are escaped once the text is rendered inside a `Debug` string
+                Regex::new(&format!(
+                    r#"(?i)\b((?:{names})\\?"?\s*[:=]\s*)(\\"(?:[^"\\]|\\[^"])*\\"|"(?:[^"\\]|\\.)*"|[^\s,;&)}}\]]+)"#
+                ))
+                .ok()
+            })
+            .flatten();
+
+        let patterns = DEFAULT_REDACT_PATTERNS
+            .into_iter()
+            .map(str::to_string)
+            .chain(
+                env::get_var_or_default("LOG_REDACT_PATTERNS", "")
+                    .split_whitespace()
+                    .map(str::to_string),
+            )
+            .filter_map(|pattern| Regex::new(&pattern).ok())
+            .collect();
+
+        Self {
+            fields,
+            assignment,
+            patterns,
+        }
+    }
+
+    fn is_secret_field(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.fields.contains(&name)
+    }
+
+    /// Patterns go first, so that `Authorization: Bearer <token>` loses the token and not
+    /// only the scheme to the field assignment
+    fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+
+        for pattern in &self.patterns {
+            if pattern.is_match(&text) {
+                text = Cow::Owned(pattern.replace_all(&text, REDACTED).into_owned());
+            }
+        }
+
+        if let Some(assignment) = self.assignment.as_ref().filter(|re| re.is_match(&text)) {
+            let redacted = assignment.replace_all(&text, |captures: &Captures| {
+                let quote = ["\\\"", "\""]
+                    .into_iter()
+                    .find(|quote| captures[2].starts_with(quote))
+                    .unwrap_or_default();
+                format!("{}{quote}{REDACTED}{quote}", &captures[1])
+            });
+            text = Cow::Owned(redacted.into_owned());
+        }
+
+        text
+    }
+
+    fn redact_fields(&self, fields: &mut Map<String, Value>) {
+        for (name, value) in fields.iter_mut() {
+            if self.is_secret_field(name) {
+                *value = Value::String(REDACTED.to_string());
+            } else {
+                self.redact_value(value);
+            }
+        }
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let Cow::Owned(redacted) = self.redact(text) {
+                    *text = redacted;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_value(value)),
+            Value::Object(fields) => self.redact_fields(fields),
+            _ => (),
+        }
+    }
+}
+
+/// Text with the configured secrets replaced by `[REDACTED]`
+pub fn redact(text: &str) -> Cow<'_, str> {
+    REDACTOR.redact(text)
+}
+
+/// Scrubs the message, the fields and the span fields of a record
+pub fn redact_record(record: &mut LogRecord) {
+    if let Cow::Owned(message) = REDACTOR.redact(&record.message) {
+        record.message = message;
+    }
+    REDACTOR.redact_fields(&mut record.fields);
+    for LogSpan { fields, .. } in &mut record.spans {
+        REDACTOR.redact_fields(fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::RedactedWriter, REDACTED, redact_record};
+    use crate::{Error, log::error, log_record::LogRecord};
+    use ::axum::{
+        http::{HeaderMap, HeaderValue},
+        response::IntoResponse,
+    };
+    use ::serde_json::{Map, Value};
+    use ::std::{
+        io::{self, Write},
+        sync::{Arc, Mutex},
+    };
+    use ::tracing_subscriber::{fmt::MakeWriter, fmt::layer, layer::SubscriberExt};
+
+    const SECRET: &str = "hunter2";
+    const TOKEN: &str = "4f3c2b1a-9e8d-7c6b-5a49-382716051234";
+
+    /// Formatted events in memory instead of stdout
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Output {
+        type Writer = Output;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Text the stdout layer prints while `log` runs
+    fn printed(log: impl FnOnce()) -> String {
+        let output = Output::default();
+        let subscriber = tracing_subscriber::registry().with(
+            layer()
+                .compact()
+                .with_writer(RedactedWriter(output.clone())),
+        );
+        tracing::subscriber::with_default(subscriber, log);
+
+        let printed = output.0.lock().unwrap().clone();
+        String::from_utf8(printed).unwrap()
+    }
+
+    /// JSON line shipped for a record holding `text` as its message and as a field
+    fn recorded(text: &str) -> String {
+        let mut fields = Map::new();
+        fields.insert("request".to_string(), Value::String(text.to_string()));
+
+        let mut record = LogRecord {
+            timestamp: "2026-01-01T00:00:00.000000Z".to_string(),
+            level: "ERROR".to_string(),
+            target: "test".to_string(),
+            message: text.to_string(),
+            fields,
+            spans: Vec::new(),
+            service: "test".to_string(),
+            host: "localhost".to_string(),
+            trace_id: None,
+        };
+        redact_record(&mut record);
+
+        serde_json::to_string(&record).unwrap()
+    }
+
+    fn assert_redacted(output: &str, secret: &str) {
+        assert!(!output.contains(secret), "secret left in {output}");
+        assert!(output.contains(REDACTED), "nothing redacted in {output}");
+    }
+
+    fn database_error() -> Error {
+        let query = format!(r#"CREATE users CONTENT {{"login":"alice","password":"{SECRET}"}}"#);
+        surrealdb::Error::Api(surrealdb::error::Api::Query(query)).into()
+    }
+
+    fn headers(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn redacts_database_errors_rendered_by_responses() {
+        assert_redacted(&recorded(&format!("{:?}", database_error())), SECRET);
+        assert_redacted(&printed(|| drop(database_error().into_response())), SECRET);
+    }
+
+    #[test]
+    fn redacts_bad_requests_rendered_by_responses() {
+        let bad_request = || Error::BadRequest(format!("login=alice&password={SECRET}"));
+
+        assert_redacted(&recorded(&format!("{:?}", bad_request())), SECRET);
+        assert_redacted(&printed(|| drop(bad_request().into_response())), SECRET);
+    }
+
+    #[test]
+    fn redacts_refresh_token_cookies() {
+        let cookie = format!("theme=dark; JWT_RT={TOKEN}; lang=en");
+        let headers = headers("cookie", &cookie);
+
+        assert_redacted(&recorded(&format!("{headers:?}")), TOKEN);
+        assert_redacted(&recorded(&format!("set cookie {cookie}")), TOKEN);
+        assert_redacted(&printed(|| error!("{headers:?}")), TOKEN);
+        assert_redacted(&printed(|| error!(cookie, "request refused")), TOKEN);
+    }
+
+    #[test]
+    fn redacts_bearer_authorization_headers() {
+        let headers = headers("authorization", &format!("Bearer {TOKEN}"));
+        let header = format!("Authorization: Bearer {TOKEN}");
+
+        assert_redacted(&recorded(&format!("{headers:?}")), TOKEN);
+        assert_redacted(&recorded(&header), TOKEN);
+        assert_redacted(&printed(|| error!("{headers:?}")), TOKEN);
+        assert_redacted(&printed(|| error!("{header}")), TOKEN);
+    }
+}
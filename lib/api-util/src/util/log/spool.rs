@@ -0,0 +1,163 @@
+use super::LOG_DROPPED_METRIC_NAME;
+use ::chrono::Utc;
+use ::std::{collections::VecDeque, path::PathBuf};
+use ::tokio::fs;
+
+const SPOOL_BYTES_METRIC_NAME: &str = "log_spool_bytes";
+const SPOOL_BATCHES_METRIC_NAME: &str = "log_spool_batches";
+const SPOOL_OLDEST_AGE_METRIC_NAME: &str = "log_spool_oldest_age_seconds";
+const SPOOL_FILE_SUFFIX: &str = ".jsonl";
+
+/// Spooled batch, the file name `<micros>-<seq>-<records>.jsonl` keeps the order of the
+/// batches and what is needed to account for them without reading them back
+struct SpoolEntry {
+    path: PathBuf,
+    size: u64,
+    records: u64,
+    created_at: i64,
+}
+
+impl SpoolEntry {
+    fn parse(path: PathBuf, size: u64) -> Option<Self> {
+        let name = path
+            .file_name()?
+            .to_str()?
+            .strip_suffix(SPOOL_FILE_SUFFIX)?;
+        let mut parts = name.split('-');
+        let created_at = parts.next()?.parse().ok()?;
+        let _seq = parts.next()?;
+        let records = parts.next()?.parse().ok()?;
+
+        Some(Self {
+            path,
+            size,
+            records,
+            created_at,
+        })
+    }
+}
+
+/// Batches the broker refused, kept on disk until they can be published in order.
+///
+/// The spool never grows past `max_size` bytes, the oldest batches make room for new ones.
+pub(super) struct Spool {
+    dir: PathBuf,
+    max_size: u64,
+    entries: VecDeque<SpoolEntry>,
+    size: u64,
+    seq: u64,
+}
+
+impl Spool {
+    /// Picks up the batches left behind by a previous run
+    pub async fn open(dir: PathBuf, max_size: u64) -> Self {
+        let mut spool = Self {
+            dir,
+            max_size,
+            entries: VecDeque::new(),
+            size: 0,
+            seq: 0,
+        };
+
+        if let Err(err) = fs::create_dir_all(&spool.dir).await {
+            eprintln!("'{}' creating log spool: {err}", spool.dir.display());
+        }
+
+        let mut entries = Vec::new();
+        if let Ok(mut dir) = fs::read_dir(&spool.dir).await {
+            while let Ok(Some(file)) = dir.next_entry().await {
+                let size = file.metadata().await.map_or(0, |metadata| metadata.len());
+                entries.extend(SpoolEntry::parse(file.path(), size));
+            }
+        }
+        entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+        spool.size = entries.iter().map(|entry| entry.size).sum();
+        spool.entries = entries.into();
+        spool.update_metrics();
+
+        spool
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Stores a batch of `records` JSON lines after the spooled ones
+    pub async fn push(&mut self, data: &[u8], records: u64) {
+        let size = data.len() as u64;
+        if size > self.max_size {
+            metrics::counter!(LOG_DROPPED_METRIC_NAME, "reason" => "spool_full").increment(records);
+            return;
+        }
+
+        while !self.entries.is_empty() && self.size + size > self.max_size {
+            if let Some(records) = self.pop_front().await {
+                metrics::counter!(LOG_DROPPED_METRIC_NAME, "reason" => "spool_full")
+                    .increment(records);
+            }
+        }
+
+        let created_at = Utc::now().timestamp_micros();
+        self.seq += 1;
+        let path = self.dir.join(format!(
+            "{created_at:020}-{:010}-{records}{SPOOL_FILE_SUFFIX}",
+            self.seq
+        ));
+
+        match fs::write(&path, data).await {
+            Ok(()) => {
+                self.size += size;
+                self.entries.push_back(SpoolEntry {
+                    path,
+                    size,
+                    records,
+                    created_at,
+                });
+            }
+            Err(err) => {
+                eprintln!("'{}' writing log spool: {err}", path.display());
+                metrics::counter!(LOG_DROPPED_METRIC_NAME, "reason" => "spool").increment(records);
+            }
+        }
+
+        self.update_metrics();
+    }
+
+    /// Oldest spooled batch with its record count, unreadable batches are discarded
+    pub async fn front(&mut self) -> Option<(Vec<u8>, u64)> {
+        loop {
+            let entry = self.entries.front()?;
+            match fs::read(&entry.path).await {
+                Ok(data) => return Some((data, entry.records)),
+                Err(err) => {
+                    eprintln!("'{}' reading log spool: {err}", entry.path.display());
+                    if let Some(records) = self.pop_front().await {
+                        metrics::counter!(LOG_DROPPED_METRIC_NAME, "reason" => "spool")
+                            .increment(records);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the oldest batch once it was published, returns its record count
+    pub async fn pop_front(&mut self) -> Option<u64> {
+        let entry = self.entries.pop_front()?;
+        self.size = self.size.saturating_sub(entry.size);
+        let _ = fs::remove_file(&entry.path).await;
+        self.update_metrics();
+
+        Some(entry.records)
+    }
+
+    pub fn update_metrics(&self) {
+        let oldest_age = self.entries.front().map_or(0, |entry| {
+            (Utc::now().timestamp_micros() - entry.created_at).max(0) / 1_000_000
+        });
+
+        metrics::gauge!(SPOOL_BYTES_METRIC_NAME).set(self.size as f64);
+        metrics::gauge!(SPOOL_BATCHES_METRIC_NAME).set(self.entries.len() as f64);
+        metrics::gauge!(SPOOL_OLDEST_AGE_METRIC_NAME).set(oldest_age as f64);
+    }
+}
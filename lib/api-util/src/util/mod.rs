@@ -5,4 +5,5 @@ pub mod log;
 pub mod server;
 pub mod migrate;
 pub mod console;
+pub mod trace;
 
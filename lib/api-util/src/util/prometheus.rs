@@ -71,7 +71,7 @@ fn setup_metrics_recorder() -> PrometheusHandle {
 /// use ::axum::{middleware, Router};
 /// use ::api_util::prometheus::{start_metrics_server, track_metrics};
 ///
-/// let app = Router::new()
+/// let app: Router = Router::new()
 ///     .route("/", axum::routing::get(|| async { "Hello, World!" }))
 ///     .layer(middleware::from_fn(track_metrics));
 /// ```
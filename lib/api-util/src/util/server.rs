@@ -28,7 +28,7 @@ pub async fn start_server(router: Router, shutdown_handle: Handle) {
 
     if let Err(err) = axum_server::bind(server_address)
         .handle(shutdown_handle)
-        .serve(router.into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
         error!("failed to start HTTP server: {}", err);
@@ -0,0 +1,206 @@
+use ::axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use ::std::future::Future;
+use ::tracing::{Instrument, info_span};
+use ::uuid::Uuid;
+
+/// W3C trace context header, `00-<trace id>-<parent id>-<flags>`
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+/// Request id header, also sent as the `correlation_id` of AMQP messages
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const TRACEPARENT_VERSION: &str = "00";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+/// Trace and request id of the operation being served, carried from one service to the
+/// next through HTTP headers and AMQP message headers.
+///
+/// Every hop continues the trace of its caller under a span id of its own, so that
+/// `trace_id` stays the same from the proxy to the logger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// 16 lowercase hex digits identifying this hop
+    pub span_id: String,
+    /// Span id of the caller, `None` when the trace starts here
+    pub parent_id: Option<String>,
+    pub flags: u8,
+    pub request_id: String,
+}
+
+impl TraceContext {
+    /// Starts a new trace
+    pub fn new() -> Self {
+        Self {
+            trace_id: Uuid::new_v4().simple().to_string(),
+            span_id: new_span_id(),
+            parent_id: None,
+            flags: 0,
+            request_id: Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// Continues the trace of a `traceparent` value, a new one is started when it is
+    /// missing or malformed. A request id which is not printable ASCII is replaced.
+    pub fn continue_from(traceparent: Option<&str>, request_id: Option<&str>) -> Self {
+        let mut context = match traceparent.and_then(parse_traceparent) {
+            Some((trace_id, parent_id, flags)) => Self {
+                trace_id: trace_id.to_string(),
+                span_id: new_span_id(),
+                parent_id: Some(parent_id.to_string()),
+                flags,
+                request_id: String::new(),
+            },
+            None => Self::new(),
+        };
+
+        if let Some(request_id) = request_id.filter(|id| is_valid_request_id(id)) {
+            context.request_id = request_id.to_string();
+        } else if context.request_id.is_empty() {
+            context.request_id = Uuid::new_v4().to_string();
+        }
+
+        context
+    }
+
+    /// Context of an incoming HTTP request
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self::continue_from(
+            headers
+                .get(TRACEPARENT_HEADER)
+                .and_then(|value| value.to_str().ok()),
+            headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok()),
+        )
+    }
+
+    /// `traceparent` value naming this hop as the parent of the next one
+    pub fn traceparent(&self) -> String {
+        format!(
+            "{TRACEPARENT_VERSION}-{}-{}-{:02x}",
+            self.trace_id, self.span_id, self.flags
+        )
+    }
+
+    /// `traceparent` and `x-request-id` headers of this hop
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::with_capacity(2);
+        if let Ok(traceparent) = HeaderValue::from_str(&self.traceparent()) {
+            headers.insert(TRACEPARENT_HEADER, traceparent);
+        }
+        if let Ok(request_id) = HeaderValue::from_str(&self.request_id) {
+            headers.insert(REQUEST_ID_HEADER, request_id);
+        }
+
+        headers
+    }
+
+    /// Context of the operation being served, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `future` with this context as the current one, outgoing AMQP messages and
+    /// proxied requests propagate it
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT.scope(self, future)
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accepts or creates the `traceparent` and `x-request-id` of a request, serves it inside
+/// a span recording them and returns them in the response headers.
+///
+/// The request headers are rewritten with the context of this hop, so that a reverse
+/// proxy forwards them to the next service as they are.
+///
+/// # Example
+///
+/// ```
+/// use ::axum::{middleware, Router};
+/// use ::api_util::trace::trace_context;
+///
+/// let app: Router = Router::new()
+///     .route("/", axum::routing::get(|| async { "Hello, World!" }))
+///     .layer(middleware::from_fn(trace_context));
+/// ```
+pub async fn trace_context(mut req: Request, next: Next) -> Response {
+    let context = TraceContext::from_headers(req.headers());
+    let span = info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.uri().path(),
+        trace_id = %context.trace_id,
+        span_id = %context.span_id,
+        parent_id = context.parent_id.as_deref(),
+        request_id = %context.request_id,
+    );
+
+    let headers = context.headers();
+    req.headers_mut().extend(headers.clone());
+    req.extensions_mut().insert(context.clone());
+
+    let mut response = context.scope(next.run(req)).instrument(span).await;
+    response.headers_mut().extend(headers);
+    response
+}
+
+/// `(trace id, parent id, flags)`, all-zero ids and the `ff` version are invalid. Versions
+/// above `00` may append fields which are ignored.
+fn parse_traceparent(value: &str) -> Option<(&str, &str, u8)> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if version.len() != 2 || !is_hex(version) || version == "ff" {
+        return None;
+    }
+    if version == TRACEPARENT_VERSION && parts.next().is_some() {
+        return None;
+    }
+    if !is_trace_id(trace_id, 32) || !is_trace_id(parent_id, 16) || flags.len() != 2 {
+        return None;
+    }
+
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some((trace_id, parent_id, flags))
+}
+
+fn is_trace_id(id: &str, length: usize) -> bool {
+    id.len() == length && is_hex(id) && id.bytes().any(|byte| byte != b'0')
+}
+
+fn is_hex(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+fn new_span_id() -> String {
+    let mut id = Uuid::new_v4().simple().to_string();
+    id.truncate(16);
+    id
+}